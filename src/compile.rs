@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use pikachu::{parse_circuit,CompiledCircuit,CompiledCircuitError,QapDomain};
+
+fn save_compiled_circuit(compiled:&CompiledCircuit,file_name:&str) -> Result<(),CompiledCircuitError>{
+    let mut file = BufWriter::new(File::create(file_name)?);
+    compiled.write_compiled_circuit(&mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+fn read_compiled_circuit(file_name:&str) -> Result<CompiledCircuit,CompiledCircuitError>{
+    let file = File::open(file_name)?;
+    CompiledCircuit::read_compiled_circuit(BufReader::new(file))
+}
+
+//Compiled circuit from a compiled file when given, else parsed and interpolated over the domain from the circuit file
+pub fn load_circuit(circuit_file:&str,compiled_file:Option<&str>,domain:QapDomain) -> CompiledCircuit{
+    if let Some(compiled_file) = compiled_file {
+        return match read_compiled_circuit(compiled_file) {
+            Ok(compiled) => compiled,
+            Err(err) => {
+                eprintln!("Failed to read compiled circuit {}: {}",compiled_file,err);
+                std::process::exit(1);
+            }
+        };
+    }
+    eprintln!("Analyzing circuit");
+    let circuit = match parse_circuit(circuit_file) {
+        Ok(circuit) => circuit,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Circuit analyzed");
+    match circuit.compile(circuit_file,domain) {
+        Ok(compiled) => compiled,
+        Err(err) => {
+            eprintln!("Failed to compile circuit {}: {}",circuit_file,err);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn main(circuit_file:&str,out_file:&str,domain:QapDomain){
+    let compiled = load_circuit(circuit_file,None,domain);
+
+    match save_compiled_circuit(&compiled,out_file) {
+        Ok(()) => {
+            println!("{} constraints over {} wires on the {} domain, fingerprint {}",compiled.circuit.operations.len(),compiled.wire_list.len(),domain,compiled.fingerprint);
+            println!("Compiled circuit written to {} !!",out_file);
+        }
+        Err(err) => {
+            eprintln!("Failed to write compiled circuit {}: {}",out_file,err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+use ark_bn254::Fr;
+use ark_ff::{batch_inversion, FftField, PrimeField, Zero};
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial, EvaluationDomain, Polynomial, Radix2EvaluationDomain};
+use ark_poly::univariate::DenseOrSparsePolynomial;
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, Compress, SerializationError, Valid, Validate};
+use sha2::{Digest, Sha256};
+use crate::file_format::{file_format_error, FileFormat, FileFormatError};
+use crate::{compute_op_points, compute_op_polynomial, compute_vanishing_polynomial, compute_wire_list, Circuit, CircuitFingerprint, ONE_WIRE};
+
+//Compiled circuit file layout, integers are little endian:
+//  magic "PIKACIR\0", version u16, then the compiled circuit with arkworks compressed encoding
+//The operand polynomials are a tag u8 followed by the polynomials of every wire (sequential)
+//or the radix-2 domain size u64, the wire polynomials of a radix-2 domain are never stored
+//The fingerprint, the polynomial counts and the polynomials themselves are checked on load
+
+/// First bytes of every compiled circuit file
+pub const COMPILED_CIRCUIT_MAGIC: [u8; 8] = *b"PIKACIR\0";
+/// Compiled circuit format written by this version
+pub const COMPILED_CIRCUIT_VERSION: u16 = 3;
+
+const COMPILED_CIRCUIT_FILE: FileFormat = FileFormat { name: "compiled circuit file", magic: COMPILED_CIRCUIT_MAGIC, version: COMPILED_CIRCUIT_VERSION };
+
+const SEQUENTIAL_TAG: u8 = 1;
+const RADIX2_TAG: u8 = 2;
+
+/// Points the constraints are interpolated at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QapDomain {
+    /// x = 1..n with Lagrange interpolation, cubic in the number of constraints
+    Sequential,
+    /// Roots of unity of the smallest radix-2 domain holding the constraints, interpolated with FFTs
+    Radix2,
+}
+
+impl FromStr for QapDomain {
+    type Err = String;
+
+    fn from_str(domain: &str) -> Result<Self, Self::Err> {
+        match domain {
+            "sequential" => Ok(QapDomain::Sequential),
+            "radix2" => Ok(QapDomain::Radix2),
+            _ => Err(format!("Unknown domain {:?}, expected sequential or radix2", domain)),
+        }
+    }
+}
+
+impl fmt::Display for QapDomain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QapDomain::Sequential => write!(f, "sequential"),
+            QapDomain::Radix2 => write!(f, "radix2"),
+        }
+    }
+}
+
+/// Operand polynomials of the wires
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperandPolynomials {
+    /// Left, right and output polynomial of every wire, interpolated at x = 1..n
+    Sequential { left: Vec<DensePolynomial<Fr>>, right: Vec<DensePolynomial<Fr>>, output: Vec<DensePolynomial<Fr>> },
+    /// Constraint i sits at the i-th root of unity, padding constraints are 0 * 0 == 0.
+    /// The polynomials are interpolated from the constraints when needed
+    Radix2(Radix2EvaluationDomain<Fr>),
+}
+
+impl OperandPolynomials {
+    pub fn domain(&self) -> QapDomain {
+        match self {
+            OperandPolynomials::Sequential { .. } => QapDomain::Sequential,
+            OperandPolynomials::Radix2(_) => QapDomain::Radix2,
+        }
+    }
+}
+
+impl CanonicalSerialize for OperandPolynomials {
+    fn serialize_with_mode<W: Write>(&self, mut writer: W, compress: Compress) -> Result<(), SerializationError> {
+        match self {
+            OperandPolynomials::Sequential { left, right, output } => {
+                SEQUENTIAL_TAG.serialize_with_mode(&mut writer, compress)?;
+                left.serialize_with_mode(&mut writer, compress)?;
+                right.serialize_with_mode(&mut writer, compress)?;
+                output.serialize_with_mode(&mut writer, compress)
+            }
+            OperandPolynomials::Radix2(domain) => {
+                RADIX2_TAG.serialize_with_mode(&mut writer, compress)?;
+                (domain.size() as u64).serialize_with_mode(&mut writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        match self {
+            OperandPolynomials::Sequential { left, right, output } => 1 + left.serialized_size(compress) + right.serialized_size(compress) + output.serialized_size(compress),
+            OperandPolynomials::Radix2(_) => 1 + 8,
+        }
+    }
+}
+
+impl Valid for OperandPolynomials {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            OperandPolynomials::Sequential { left, right, output } => {
+                left.check()?;
+                right.check()?;
+                output.check()
+            }
+            OperandPolynomials::Radix2(_) => Ok(()),
+        }
+    }
+}
+
+impl CanonicalDeserialize for OperandPolynomials {
+    fn deserialize_with_mode<R: Read>(mut reader: R, compress: Compress, validate: Validate) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            SEQUENTIAL_TAG => Ok(OperandPolynomials::Sequential {
+                left: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+                right: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+                output: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            }),
+            RADIX2_TAG => {
+                let size = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+                //Only an exact power of two the field supports
+                match Radix2EvaluationDomain::new(size as usize) {
+                    Some(domain) if domain.size() as u64 == size => Ok(OperandPolynomials::Radix2(domain)),
+                    _ => Err(SerializationError::InvalidData),
+                }
+            }
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// Circuit with its QAP polynomials, interpolated once and shared by the setup and the prover
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CompiledCircuit {
+    pub fingerprint: CircuitFingerprint,
+    /// Circuit file it was compiled from, the constraint lines refer to it
+    pub source: String,
+    pub circuit: Circuit,
+    /// Wire of every operand polynomial, the constant one wire first
+    pub wire_list: Vec<String>,
+    pub operand_polynomials: OperandPolynomials,
+    pub vanishing_polynomial: DensePolynomial<Fr>,
+}
+
+/// Error reading or writing a compiled circuit file
+#[derive(Debug)]
+pub enum CompiledCircuitError {
+    Format(FileFormatError),
+    Inconsistent(String),
+    /// More constraints than the largest radix-2 domain of the field holds
+    TooManyConstraints { constraints: usize, max: usize },
+}
+
+impl fmt::Display for CompiledCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompiledCircuitError::Format(err) => write!(f, "{}", err),
+            CompiledCircuitError::Inconsistent(msg) => write!(f, "Corrupted compiled circuit: {}", msg),
+            CompiledCircuitError::TooManyConstraints { constraints, max } => write!(f, "{} constraints don't fit the radix-2 domain, it holds at most {}", constraints, max),
+        }
+    }
+}
+
+impl std::error::Error for CompiledCircuitError {}
+
+file_format_error!(CompiledCircuitError);
+
+impl Circuit {
+    /// Fingerprint of the circuit interpolated over the domain, keys made over another domain don't fit.
+    /// The sequential domain keeps the plain circuit fingerprint
+    pub fn domain_fingerprint(&self, domain: QapDomain) -> CircuitFingerprint {
+        let fingerprint = self.fingerprint();
+        match domain {
+            QapDomain::Sequential => fingerprint,
+            QapDomain::Radix2 => {
+                let mut hasher = Sha256::new();
+                hasher.update(b"pikachu radix2 domain");
+                hasher.update(fingerprint.0);
+                CircuitFingerprint(hasher.finalize().into())
+            }
+        }
+    }
+
+    /// Interpolate the operand polynomials of every wire and the vanishing polynomial over the domain
+    pub fn compile(&self, source: &str, domain: QapDomain) -> Result<CompiledCircuit, CompiledCircuitError> {
+        let (operand_polynomials, vanishing_polynomial) = match domain {
+            QapDomain::Sequential => {
+                //Same wire list for every operand
+                let (left_op_points, _) = compute_op_points(self.operations.clone(), 0);
+                let (right_op_points, _) = compute_op_points(self.operations.clone(), 1);
+                let (output_op_points, _) = compute_op_points(self.operations.clone(), 2);
+
+                //Lagrange interpolation
+                let (left, _) = compute_op_polynomial(left_op_points);
+                let (right, _) = compute_op_polynomial(right_op_points);
+                let (output, _) = compute_op_polynomial(output_op_points);
+                (OperandPolynomials::Sequential { left, right, output }, compute_vanishing_polynomial(self.operations.len()))
+            }
+            QapDomain::Radix2 => {
+                let domain = match Radix2EvaluationDomain::<Fr>::new(self.operations.len()) {
+                    Some(domain) => domain,
+                    None => return Err(CompiledCircuitError::TooManyConstraints { constraints: self.operations.len(), max: 1 << <Fr as FftField>::TWO_ADICITY }),
+                };
+                //x^n - 1
+                (OperandPolynomials::Radix2(domain), domain.vanishing_polynomial().into())
+            }
+        };
+
+        Ok(CompiledCircuit {
+            fingerprint: self.domain_fingerprint(domain),
+            source: String::from(source),
+            circuit: self.clone(),
+            wire_list: compute_wire_list(&self.operations),
+            operand_polynomials,
+            vanishing_polynomial,
+        })
+    }
+}
+
+//Lagrange basis polynomials of x = 1..n evaluated at z, and the vanishing polynomial at z
+fn sequential_lagrange_coefficients(n: usize, z: Fr) -> (Vec<Fr>, Fr) {
+    let vanishing: Fr = (1..=n as u64).map(|i| z - Fr::from(i)).product();
+    //prod_{j != i} (i - j) = (i - 1)! * (n - i)! * (-1)^(n - i)
+    let mut factorials = vec![Fr::from(1u64)];
+    for k in 1..n as u64 {
+        factorials.push(factorials[k as usize - 1] * Fr::from(k));
+    }
+    let mut denominators: Vec<Fr> = (1..=n)
+        .map(|i| {
+            let weight = factorials[i - 1] * factorials[n - i];
+            let weight = if (n - i) % 2 == 1 { -weight } else { weight };
+            (z - Fr::from(i as u64)) * weight
+        })
+        .collect();
+    batch_inversion(&mut denominators);
+    (denominators.into_iter().map(|inverse| vanishing * inverse).collect(), vanishing)
+}
+
+//Sum of the polynomials weighted by the wire values
+fn compute_final_polynomial(polynomial_array: &[DensePolynomial<Fr>], values: &[Fr]) -> DensePolynomial<Fr> {
+    let mut final_polynomial: DensePolynomial<Fr> = DensePolynomial::from_coefficients_vec(vec![Fr::zero()]);
+    for (polynomial, value) in polynomial_array.iter().zip(values.iter()) {
+        final_polynomial = &final_polynomial + &(polynomial * *value);
+    }
+    final_polynomial
+}
+
+impl CompiledCircuit {
+    pub fn domain(&self) -> QapDomain {
+        self.operand_polynomials.domain()
+    }
+
+    //Coefficient of every wire in the left, right and output operand of every constraint
+    fn for_each_term(&self, mut f: impl FnMut(usize, usize, usize, Fr)) {
+        let wire_index: HashMap<&str, usize> = self.wire_list.iter().enumerate().map(|(i, variable)| (variable.as_str(), i)).collect();
+        for (constraint, operation) in self.circuit.operations.iter().enumerate() {
+            for (operand, terms) in [&operation.left, &operation.right, &operation.output].into_iter().enumerate() {
+                for (coefficient, variable) in terms {
+                    f(constraint, operand, wire_index[variable.as_str()], *coefficient);
+                }
+            }
+        }
+    }
+
+    /// Left, right and output polynomial of every wire evaluated at x
+    pub fn wire_evaluations(&self, x: &Fr) -> [Vec<Fr>; 3] {
+        match &self.operand_polynomials {
+            OperandPolynomials::Sequential { left, right, output } => {
+                [left, right, output].map(|polynomials| polynomials.iter().map(|polynomial| polynomial.evaluate(x)).collect())
+            }
+            OperandPolynomials::Radix2(domain) => self.constraint_evaluations(&domain.evaluate_all_lagrange_coefficients(*x)),
+        }
+    }
+
+    //Wire polynomials are sums of Lagrange basis polynomials weighted by the coefficients
+    fn constraint_evaluations(&self, lagrange: &[Fr]) -> [Vec<Fr>; 3] {
+        let mut evaluations = [0, 1, 2].map(|_| vec![Fr::zero(); self.wire_list.len()]);
+        self.for_each_term(|constraint, operand, wire, coefficient| evaluations[operand][wire] += coefficient * lagrange[constraint]);
+        evaluations
+    }
+
+    /// Left, right and output polynomial of the circuit for the wire values in wire list order
+    pub fn operand_polynomials(&self, values: &[Fr]) -> [DensePolynomial<Fr>; 3] {
+        match &self.operand_polynomials {
+            OperandPolynomials::Sequential { left, right, output } => [left, right, output].map(|polynomials| compute_final_polynomial(polynomials, values)),
+            OperandPolynomials::Radix2(domain) => {
+                //Operand values of every constraint, then IFFT
+                let mut evaluations = [0, 1, 2].map(|_| vec![Fr::zero(); domain.size()]);
+                self.for_each_term(|constraint, operand, wire, coefficient| evaluations[operand][constraint] += coefficient * values[wire]);
+                evaluations.map(|evaluations| DensePolynomial::from_coefficients_vec(domain.ifft(&evaluations)))
+            }
+        }
+    }
+
+    /// Quotient of the polynomial divided by the vanishing polynomial, the remainder is dropped
+    pub fn divide_by_vanishing_polynomial(&self, polynomial: &DensePolynomial<Fr>) -> DensePolynomial<Fr> {
+        match &self.operand_polynomials {
+            OperandPolynomials::Sequential { .. } => {
+                let (quotient, _) = DenseOrSparsePolynomial::from(polynomial).divide_with_q_and_r(&DenseOrSparsePolynomial::from(&self.vanishing_polynomial)).unwrap();
+                quotient
+            }
+            OperandPolynomials::Radix2(domain) => {
+                let (quotient, _) = polynomial.divide_by_vanishing_poly(*domain).unwrap();
+                quotient
+            }
+        }
+    }
+
+    pub fn write_compiled_circuit<W: Write>(&self, mut writer: W) -> Result<(), CompiledCircuitError> {
+        COMPILED_CIRCUIT_FILE.write_header(&mut writer)?;
+        self.serialize_with_mode(&mut writer, Compress::Yes)?;
+        Ok(())
+    }
+
+    pub fn read_compiled_circuit<R: Read>(mut reader: R) -> Result<Self, CompiledCircuitError> {
+        COMPILED_CIRCUIT_FILE.read_header(&mut reader)?;
+        let compiled = Self::deserialize_with_mode(&mut reader, Compress::Yes, Validate::Yes)?;
+        compiled.check_consistency()?;
+        Ok(compiled)
+    }
+
+    //The stored fingerprint must be the one of the constraints, with a polynomial per wire and a root per constraint
+    fn check_consistency(&self) -> Result<(), CompiledCircuitError> {
+        let constraints = self.circuit.operations.len();
+        if self.circuit.lines.len() != constraints {
+            return Err(CompiledCircuitError::Inconsistent(format!("{} source lines for {} constraints", self.circuit.lines.len(), constraints)));
+        }
+        if self.wire_list != compute_wire_list(&self.circuit.operations) {
+            return Err(CompiledCircuitError::Inconsistent(String::from("wire list doesn't match the constraints")));
+        }
+        //The one wire first, then distinct wires of the constraints
+        let public_variables = &self.circuit.public_variables;
+        if public_variables.first().map(String::as_str) != Some(ONE_WIRE) {
+            return Err(CompiledCircuitError::Inconsistent(String::from("public variables don't start with the one wire")));
+        }
+        for (i, variable) in public_variables.iter().enumerate() {
+            if !self.wire_list.contains(variable) || public_variables[..i].contains(variable) {
+                return Err(CompiledCircuitError::Inconsistent(format!("public variable {:?} isn't a distinct wire of the constraints", variable)));
+            }
+        }
+        let roots = match &self.operand_polynomials {
+            OperandPolynomials::Sequential { left, right, output } => {
+                for polynomials in [left, right, output] {
+                    if polynomials.len() != self.wire_list.len() {
+                        return Err(CompiledCircuitError::Inconsistent(format!("{} operand polynomials for {} wires", polynomials.len(), self.wire_list.len())));
+                    }
+                }
+                constraints
+            }
+            OperandPolynomials::Radix2(domain) => {
+                if domain.size() < constraints {
+                    return Err(CompiledCircuitError::Inconsistent(format!("radix-2 domain of size {} for {} constraints", domain.size(), constraints)));
+                }
+                domain.size()
+            }
+        };
+        if self.vanishing_polynomial.coeffs.len() != roots + 1 {
+            return Err(CompiledCircuitError::Inconsistent(format!("vanishing polynomial of degree {} for {} roots", self.vanishing_polynomial.coeffs.len().saturating_sub(1), roots)));
+        }
+        let fingerprint = self.circuit.domain_fingerprint(self.domain());
+        if self.fingerprint != fingerprint {
+            return Err(CompiledCircuitError::Inconsistent(format!("fingerprint {} but the constraints hash to {}", self.fingerprint, fingerprint)));
+        }
+        self.check_polynomials()
+    }
+
+    //Polynomials of degree below n that agree with the constraints at a point they can't choose are the interpolated ones.
+    //The point is a hash of the polynomials, so the check costs one evaluation of every polynomial instead of a new interpolation
+    fn check_polynomials(&self) -> Result<(), CompiledCircuitError> {
+        let constraints = self.circuit.operations.len();
+        match &self.operand_polynomials {
+            OperandPolynomials::Sequential { left, right, output } => {
+                if let Some(polynomial) = [left, right, output].into_iter().flatten().find(|polynomial| polynomial.coeffs.len() > constraints) {
+                    return Err(CompiledCircuitError::Inconsistent(format!("operand polynomial of degree {} for {} constraints", polynomial.coeffs.len() - 1, constraints)));
+                }
+                let mut hasher = Sha256::new();
+                hasher.update(b"pikachu compiled circuit check");
+                self.operand_polynomials.serialize_compressed(&mut hasher)?;
+                self.vanishing_polynomial.serialize_compressed(&mut hasher)?;
+                let z = Fr::from_le_bytes_mod_order(&hasher.finalize());
+
+                let (lagrange, vanishing) = sequential_lagrange_coefficients(constraints, z);
+                if self.vanishing_polynomial.evaluate(&z) != vanishing {
+                    return Err(CompiledCircuitError::Inconsistent(String::from("vanishing polynomial doesn't vanish on the constraints")));
+                }
+                if self.wire_evaluations(&z) != self.constraint_evaluations(&lagrange) {
+                    return Err(CompiledCircuitError::Inconsistent(String::from("operand polynomials don't match the constraints")));
+                }
+            }
+            OperandPolynomials::Radix2(domain) => {
+                if self.vanishing_polynomial != domain.vanishing_polynomial().into() {
+                    return Err(CompiledCircuitError::Inconsistent(String::from("vanishing polynomial isn't the one of the radix-2 domain")));
+                }
+            }
+        }
+        Ok(())
+    }
+}
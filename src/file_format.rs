@@ -0,0 +1,85 @@
+use std::fmt;
+use std::io::{Read, Write};
+use ark_serialize::SerializationError;
+
+//Every pikachu file starts with an 8 byte magic and the format version u16 little endian,
+//the key file and the compiled circuit file only differ in what follows
+
+/// Magic and version of a pikachu file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileFormat {
+    /// Name in error messages
+    pub name: &'static str,
+    pub magic: [u8; 8],
+    pub version: u16,
+}
+
+/// Error reading or writing the header or the arkworks body of a pikachu file
+#[derive(Debug)]
+pub enum FileFormatError {
+    Io(std::io::Error),
+    BadMagic { format: &'static str },
+    UnsupportedVersion { format: &'static str, expected: u16, found: u16 },
+    Serialization(SerializationError),
+}
+
+impl fmt::Display for FileFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileFormatError::Io(err) => write!(f, "{}", err),
+            FileFormatError::BadMagic { format } => write!(f, "Not a pikachu {}", format),
+            FileFormatError::UnsupportedVersion { format, expected, found } => write!(f, "Unsupported {} version {}, expected {}", format, found, expected),
+            FileFormatError::Serialization(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for FileFormatError {}
+
+impl FileFormat {
+    pub fn write_header<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.magic)?;
+        writer.write_all(&self.version.to_le_bytes())
+    }
+
+    //Only the current version is read, older files have to be generated again
+    pub fn read_header<R: Read>(&self, reader: &mut R) -> Result<(), FileFormatError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(FileFormatError::Io)?;
+        if magic != self.magic {
+            return Err(FileFormatError::BadMagic { format: self.name });
+        }
+        let mut version = [0u8; 2];
+        reader.read_exact(&mut version).map_err(FileFormatError::Io)?;
+        let version = u16::from_le_bytes(version);
+        if version != self.version {
+            return Err(FileFormatError::UnsupportedVersion { format: self.name, expected: self.version, found: version });
+        }
+        Ok(())
+    }
+}
+
+//Conversions of a file error with a Format(FileFormatError) variant, so `?` works on io and serialization errors
+macro_rules! file_format_error {
+    ($error:ident) => {
+        impl From<FileFormatError> for $error {
+            fn from(err: FileFormatError) -> Self {
+                $error::Format(err)
+            }
+        }
+
+        impl From<std::io::Error> for $error {
+            fn from(err: std::io::Error) -> Self {
+                $error::Format(FileFormatError::Io(err))
+            }
+        }
+
+        impl From<SerializationError> for $error {
+            fn from(err: SerializationError) -> Self {
+                $error::Format(FileFormatError::Serialization(err))
+            }
+        }
+    };
+}
+
+pub(crate) use file_format_error;
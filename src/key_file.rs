@@ -0,0 +1,303 @@
+use std::fmt;
+use std::io::{Read, Write};
+use ark_bn254::{g1, g2};
+use ark_ec::short_weierstrass::Projective;
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, Compress, SerializationError, Validate};
+use crate::{validate_point, CircuitFingerprint, InvalidPoint, ProvingKey, VerifyingKey};
+use crate::file_format::{file_format_error, FileFormat, FileFormatError};
+
+//Key file layout, integers are little endian:
+//  magic "PIKAKEY\0", version u16, key kind u8, point encoding u8, section count u32
+//  every section: group tag u8, element count u64, the elements
+//Sections follow the field order of the key struct, single elements are sections of count 1
+//Points are affine, compressed unless the encoding says otherwise
+//Points are validated on load, only the per-wire sections may hold the identity
+
+/// First bytes of every key file
+pub const KEY_FILE_MAGIC: [u8; 8] = *b"PIKAKEY\0";
+/// Key file format written by this version
+pub const KEY_FILE_VERSION: u16 = 4;
+
+const KEY_FILE: FileFormat = FileFormat { name: "key file", magic: KEY_FILE_MAGIC, version: KEY_FILE_VERSION };
+
+/// Which key a key file holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyKind {
+    Proving = 1,
+    Verifying = 2,
+}
+
+/// Type of the elements of a key file section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupTag {
+    G1 = 1,
+    G2 = 2,
+    Name = 3,
+    Fingerprint = 4,
+}
+
+/// Error reading or writing a key file
+#[derive(Debug)]
+pub enum KeyFileError {
+    Format(FileFormatError),
+    UnknownEncoding(u8),
+    WrongKind { expected: KeyKind, found: u8 },
+    SectionCount { expected: u32, found: u32 },
+    WrongGroup { section: u32, expected: GroupTag, found: u8 },
+    WrongElementCount { section: u32, expected: u64, found: u64 },
+    InvalidPoint { section: u32, element: u64, reason: InvalidPoint },
+}
+
+impl fmt::Display for KeyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyKind::Proving => write!(f, "proving key"),
+            KeyKind::Verifying => write!(f, "verification key"),
+        }
+    }
+}
+
+impl fmt::Display for KeyFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyFileError::Format(err) => write!(f, "{}", err),
+            KeyFileError::UnknownEncoding(encoding) => write!(f, "Unknown point encoding {} in the key file", encoding),
+            KeyFileError::WrongKind { expected, found } => match [KeyKind::Proving, KeyKind::Verifying].iter().find(|kind| **kind as u8 == *found) {
+                Some(kind) => write!(f, "Expected a {}, found a {}", expected, kind),
+                None => write!(f, "Expected a {}, found unknown key kind {}", expected, found),
+            },
+            KeyFileError::SectionCount { expected, found } => write!(f, "Expected {} sections in the key file, found {}", expected, found),
+            KeyFileError::WrongGroup { section, expected, found } => write!(f, "Section {} of the key file should hold {:?} elements, found group tag {}", section, expected, found),
+            KeyFileError::WrongElementCount { section, expected, found } => write!(f, "Section {} of the key file should hold {} element, found {}", section, expected, found),
+            KeyFileError::InvalidPoint { section, element, reason } => write!(f, "Element {} of section {} of the key file is invalid: {}", element, section, reason),
+        }
+    }
+}
+
+impl std::error::Error for KeyFileError {}
+
+file_format_error!(KeyFileError);
+
+//Spelled with the curve configs, the G1Projective/G2Projective aliases look alike to coherence
+type G = Projective<g1::Config>;
+type G2 = Projective<g2::Config>;
+
+//Element types that can be stored in a section
+trait Element: CanonicalSerialize + CanonicalDeserialize {
+    const TAG: GroupTag;
+    fn validate(&self, identity_allowed: bool) -> Result<(), InvalidPoint>;
+}
+
+impl Element for G {
+    const TAG: GroupTag = GroupTag::G1;
+    fn validate(&self, identity_allowed: bool) -> Result<(), InvalidPoint> {
+        validate_point(self, identity_allowed)
+    }
+}
+
+impl Element for G2 {
+    const TAG: GroupTag = GroupTag::G2;
+    fn validate(&self, identity_allowed: bool) -> Result<(), InvalidPoint> {
+        validate_point(self, identity_allowed)
+    }
+}
+
+impl Element for String {
+    const TAG: GroupTag = GroupTag::Name;
+    fn validate(&self, _identity_allowed: bool) -> Result<(), InvalidPoint> {
+        Ok(())
+    }
+}
+
+impl Element for CircuitFingerprint {
+    const TAG: GroupTag = GroupTag::Fingerprint;
+    fn validate(&self, _identity_allowed: bool) -> Result<(), InvalidPoint> {
+        Ok(())
+    }
+}
+
+fn write_section<E: Element, W: Write>(writer: &mut W, elements: &[E], compress: Compress) -> Result<(), KeyFileError> {
+    writer.write_all(&[E::TAG as u8])?;
+    writer.write_all(&(elements.len() as u64).to_le_bytes())?;
+    for element in elements {
+        element.serialize_with_mode(&mut *writer, compress)?;
+    }
+    Ok(())
+}
+
+fn read_section<E: Element, R: Read>(reader: &mut R, section: u32, compress: Compress, identity_allowed: bool) -> Result<Vec<E>, KeyFileError> {
+    let mut tag = [0u8; 1];
+    reader.read_exact(&mut tag)?;
+    if tag[0] != E::TAG as u8 {
+        return Err(KeyFileError::WrongGroup { section, expected: E::TAG, found: tag[0] });
+    }
+    let mut count = [0u8; 8];
+    reader.read_exact(&mut count)?;
+    //No preallocation, a corrupt count runs into the end of the file instead of exhausting memory
+    let mut elements = Vec::new();
+    for element in 0..u64::from_le_bytes(count) {
+        let value = E::deserialize_with_mode(&mut *reader, compress, Validate::No)?;
+        value.validate(identity_allowed).map_err(|reason| KeyFileError::InvalidPoint { section, element, reason })?;
+        elements.push(value);
+    }
+    Ok(elements)
+}
+
+//A key field stored as one section
+trait Section: Sized {
+    fn write<W: Write>(&self, writer: &mut W, compress: Compress) -> Result<(), KeyFileError>;
+    fn read<R: Read>(reader: &mut R, section: u32, compress: Compress) -> Result<Self, KeyFileError>;
+}
+
+impl<E: Element> Section for Vec<E> {
+    fn write<W: Write>(&self, writer: &mut W, compress: Compress) -> Result<(), KeyFileError> {
+        write_section(writer, self, compress)
+    }
+
+    fn read<R: Read>(reader: &mut R, section: u32, compress: Compress) -> Result<Self, KeyFileError> {
+        read_section(reader, section, compress, true)
+    }
+}
+
+macro_rules! single_element_section {
+    ($element:ty) => {
+        impl Section for $element {
+            fn write<W: Write>(&self, writer: &mut W, compress: Compress) -> Result<(), KeyFileError> {
+                write_section(writer, std::slice::from_ref(self), compress)
+            }
+
+            fn read<R: Read>(reader: &mut R, section: u32, compress: Compress) -> Result<Self, KeyFileError> {
+                let mut elements = read_section::<$element, R>(reader, section, compress, false)?;
+                if elements.len() != 1 {
+                    return Err(KeyFileError::WrongElementCount { section, expected: 1, found: elements.len() as u64 });
+                }
+                Ok(elements.remove(0))
+            }
+        }
+    };
+}
+
+single_element_section!(G);
+single_element_section!(G2);
+single_element_section!(CircuitFingerprint);
+
+//Point encoding byte of the header
+const COMPRESSED: u8 = 0;
+const UNCOMPRESSED: u8 = 1;
+
+fn write_header<W: Write>(writer: &mut W, kind: KeyKind, compress: Compress, sections: u32) -> Result<(), KeyFileError> {
+    KEY_FILE.write_header(writer)?;
+    writer.write_all(&[kind as u8])?;
+    writer.write_all(&[if compress == Compress::Yes { COMPRESSED } else { UNCOMPRESSED }])?;
+    writer.write_all(&sections.to_le_bytes())?;
+    Ok(())
+}
+
+//Point encoding of the file
+fn read_header<R: Read>(reader: &mut R, kind: KeyKind, sections: u32) -> Result<Compress, KeyFileError> {
+    //Older versions carry no circuit fingerprint, their keys have to be generated again
+    KEY_FILE.read_header(reader)?;
+    let mut found_kind = [0u8; 1];
+    reader.read_exact(&mut found_kind)?;
+    if found_kind[0] != kind as u8 {
+        return Err(KeyFileError::WrongKind { expected: kind, found: found_kind[0] });
+    }
+    let mut encoding = [0u8; 1];
+    reader.read_exact(&mut encoding)?;
+    let compress = match encoding[0] {
+        COMPRESSED => Compress::Yes,
+        UNCOMPRESSED => Compress::No,
+        _ => return Err(KeyFileError::UnknownEncoding(encoding[0])),
+    };
+    let mut found_sections = [0u8; 4];
+    reader.read_exact(&mut found_sections)?;
+    let found_sections = u32::from_le_bytes(found_sections);
+    if found_sections != sections {
+        return Err(KeyFileError::SectionCount { expected: sections, found: found_sections });
+    }
+    Ok(compress)
+}
+
+/// Key stored in the versioned key file format
+pub trait KeyFile: Sized {
+    /// Kind recorded in the header
+    const KIND: KeyKind;
+
+    /// Write the key in the versioned key file format with compressed or uncompressed points
+    fn write_key_file<W: Write>(&self, writer: W, compress: Compress) -> Result<(), KeyFileError>;
+
+    /// Read a key written by `write_key_file`, in either point encoding
+    fn read_key_file<R: Read>(reader: R) -> Result<Self, KeyFileError>;
+}
+
+//Key file reader and writer, the sections are the listed fields in order
+macro_rules! key_file {
+    ($key:ident, $kind:expr, [$($field:ident),* $(,)?]) => {
+        impl KeyFile for $key {
+            const KIND: KeyKind = $kind;
+
+            fn write_key_file<W: Write>(&self, mut writer: W, compress: Compress) -> Result<(), KeyFileError> {
+                let sections = [$(stringify!($field)),*].len() as u32;
+                write_header(&mut writer, Self::KIND, compress, sections)?;
+                $(self.$field.write(&mut writer, compress)?;)*
+                Ok(())
+            }
+
+            fn read_key_file<R: Read>(mut reader: R) -> Result<Self, KeyFileError> {
+                let sections = [$(stringify!($field)),*].len() as u32;
+                let compress = read_header(&mut reader, Self::KIND, sections)?;
+                let mut section = 0;
+                Ok($key {
+                    $($field: {
+                        section += 1;
+                        Section::read(&mut reader, section, compress)?
+                    },)*
+                })
+            }
+        }
+    };
+}
+
+key_file!(ProvingKey, KeyKind::Proving, [
+    circuit_fingerprint,
+    gl_left_operand_poly_eval,
+    gr_right_operand_poly_eval,
+    go_output_operand_poly_eval,
+    gl_alpha_left_operand_poly_eval,
+    gr_alpha_right_operand_poly_eval,
+    go_alpha_output_operand_poly_eval,
+    g_beta_operand_poly_eval,
+    gl_t_eval,
+    gr_t_eval,
+    go_t_eval,
+    gl_alphal_t_eval,
+    gr_alphar_t_eval,
+    go_alphao_t_eval,
+    gl_beta_t_eval,
+    gr_beta_t_eval,
+    go_beta_t_eval,
+    g_alphal,
+    g_alphar,
+    g_alphao,
+    gr2_t_eval,
+    gr2_alphar_t_eval,
+    gr2_right_operand_poly_eval,
+    gr2_alpha_right_operand_poly_eval,
+    g2sk,
+]);
+
+key_file!(VerifyingKey, KeyKind::Verifying, [
+    circuit_fingerprint,
+    public_variables,
+    g,
+    go_t_eval,
+    g_alphar,
+    gl_public_left_operand_poly_eval,
+    go_public_output_operand_poly_eval,
+    g2,
+    g2_alphal,
+    g2_alphao,
+    g2_gamma,
+    g2_beta_gamma,
+    gr2_public_right_operand_poly_eval,
+]);
@@ -1,10 +1,24 @@
-use ark_ff::{PrimeField,Zero};
+use ark_ff::{Field,PrimeField,Zero,One};
+use num_bigint::BigUint;
 use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
 use std::{ops::Mul, vec};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use sha2::{Digest, Sha256};
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
 use std::path::Path;
 use std::fs::File;
 use ark_bn254::Fr;
 use std::io::Read;
+use std::fmt;
+
+mod snark;
+pub use snark::{setup, prove, verify, validate_point, ProvingKey, VerifyingKey, Proof, Witness, PublicInputs, Error, ProofError, InvalidPoint, VerificationError, Operand};
+mod file_format;
+pub use file_format::FileFormatError;
+mod key_file;
+pub use key_file::{KeyFile, KeyFileError, KeyKind, GroupTag, KEY_FILE_MAGIC, KEY_FILE_VERSION};
+mod compiled_circuit;
+pub use compiled_circuit::{CompiledCircuit, CompiledCircuitError, OperandPolynomials, QapDomain, COMPILED_CIRCUIT_MAGIC, COMPILED_CIRCUIT_VERSION};
 
 
 pub fn lagrange_interpolation_polynomial<F: PrimeField>(points: &[(F, F)]) -> DensePolynomial<F> {
@@ -29,219 +43,740 @@ pub fn lagrange_interpolation_polynomial<F: PrimeField>(points: &[(F, F)]) -> De
 
 }
 
-pub fn shield_brack_parser(op: &str) -> (String, String) {
-    let op_arr: Vec<char> = op.chars().collect();
-    let digits: String = op.chars().filter(|c: &char| c.is_numeric()).collect();
-    let operand: String = op.chars().filter(|c| c.is_alphabetic()).collect();
+/// Name of the constant-one wire, it is always wire 0 and its value is fixed to 1
+pub const ONE_WIRE: &str = "one";
+
+/// Suffix of the inverse wire introduced for every divisor, its value is computed by the prover
+pub const INVERSE_WIRE_SUFFIX: &str = "^-1";
+
+/// Name of the inverse wire backing the non-zero check of a divisor
+pub fn inverse_wire(variable: &str) -> String {
+    String::from(variable) + INVERSE_WIRE_SUFFIX
+}
+
+/// Parsed constraint of the form left * right == output.
+/// Each side is a list of (coefficient, variable) terms.
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Operation {
+    pub left: Vec<(Fr, String)>,
+    pub right: Vec<(Fr, String)>,
+    pub output: Vec<(Fr, String)>,
+}
+
+/// Parsed circuit: the constraints and the public wires of the statement
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Circuit {
+    pub operations: Vec<Operation>,
+    /// Public variables in declaration order, the constant one wire always comes first
+    pub public_variables: Vec<String>,
+    /// 1-based source line of every operation, a division and its non-zero check share the line
+    pub lines: Vec<usize>,
+}
+
+impl Circuit {
+    pub fn is_public(&self, variable: &str) -> bool {
+        self.public_variables.iter().any(|v| v == variable)
+    }
+
+    /// SHA-256 of the compiled constraint system: the public variables and, per constraint, the
+    /// coefficient of every wire in each operand. Formatting, comments and private names don't change it
+    pub fn fingerprint(&self) -> CircuitFingerprint {
+        let wire_list = compute_wire_list(&self.operations);
+        let wire_index: HashMap<&str, usize> = wire_list.iter().enumerate().map(|(i, variable)| (variable.as_str(), i)).collect();
+        let mut hasher = Sha256::new();
+        hasher.update(b"pikachu circuit v1");
+        hasher.update((wire_list.len() as u64).to_le_bytes());
+        hasher.update((self.public_variables.len() as u64).to_le_bytes());
+        for variable in &self.public_variables {
+            hasher.update((variable.len() as u64).to_le_bytes());
+            hasher.update(variable.as_bytes());
+        }
+        hasher.update((self.operations.len() as u64).to_le_bytes());
+        for operation in &self.operations {
+            for terms in [&operation.left, &operation.right, &operation.output] {
+                //Repeated variables are summed, wires with a zero coefficient left out
+                let mut coefficients: BTreeMap<usize, Fr> = BTreeMap::new();
+                for (coefficient, variable) in terms {
+                    *coefficients.entry(wire_index[variable.as_str()]).or_insert(Fr::zero()) += coefficient;
+                }
+                coefficients.retain(|_, coefficient| !coefficient.is_zero());
+                hasher.update((coefficients.len() as u64).to_le_bytes());
+                for (wire, coefficient) in coefficients {
+                    hasher.update((wire as u64).to_le_bytes());
+                    coefficient.serialize_uncompressed(&mut hasher).unwrap();
+                }
+            }
+        }
+        CircuitFingerprint(hasher.finalize().into())
+    }
+
+    /// Evaluate every constraint against the witness and return the ones that don't hold
+    pub fn check_witness(&self, witness_values: &HashMap<String, Fr>) -> Result<Vec<UnsatisfiedConstraint>, String> {
+        let mut unsatisfied: Vec<UnsatisfiedConstraint> = Vec::new();
+        for (operation, line) in self.operations.iter().zip(self.lines.iter()) {
+            let mut values: Vec<Fr> = Vec::new();
+            for terms in [&operation.left, &operation.right, &operation.output] {
+                let (value, unknown) = split_known(terms, witness_values);
+                if let Some((variable, _)) = unknown.first() {
+                    return Err(format!("Variable: {:?} not found in the witness file", variable));
+                }
+                values.push(value);
+            }
+            if values[0] * values[1] != values[2] {
+                unsatisfied.push(UnsatisfiedConstraint { line: *line, left: values[0], right: values[1], output: values[2] });
+            }
+        }
+        Ok(unsatisfied)
+    }
+}
+
+/// Hash identifying a compiled circuit, stored in the keys and proofs made for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CircuitFingerprint(pub [u8; 32]);
+
+impl fmt::Display for CircuitFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Constraint that doesn't hold for a witness, with the value of every operand
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsatisfiedConstraint {
+    pub line: usize,
+    pub left: Fr,
+    pub right: Fr,
+    pub output: Fr,
+}
+
+impl fmt::Display for UnsatisfiedConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "constraint on line {} is not satisfied: left = {}, right = {}, output = {} but left * right = {}",
+            self.line,
+            format_field_element(self.left),
+            format_field_element(self.right),
+            format_field_element(self.output),
+            format_field_element(self.left * self.right)
+        )
+    }
+}
 
-    if op_arr[0] == '[' {
-        let sub = '-';
-        let ref_digits = &digits;
-        let coeff = sub.to_string() + ref_digits;
-        return (coeff, operand);
+/// Error raised while parsing a circuit, points at the offending text of the source line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub file: String,
+    /// 1-based line number, 0 when the error is not tied to a line
+    pub line: u32,
+    /// 1-based column of the offending text
+    pub column: u32,
+    pub text: String,
+    pub source_line: String,
+    pub message: String,
+    pub hint: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    //Rendered like a compiler diagnostic
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        if self.line == 0 {
+            writeln!(f, " --> {}", self.file)?;
+            return write!(f, "  = hint: {}", self.hint);
+        }
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        let marker = "^".repeat(self.text.chars().count().max(1));
+        writeln!(f, "{}--> {}:{}:{}", gutter, self.file, self.line, self.column)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line.replace('\t', " "))?;
+        writeln!(f, "{} | {}{}", gutter, " ".repeat(self.column as usize - 1), marker)?;
+        write!(f, "{} = hint: {}", gutter, self.hint)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+//Line of the circuit being parsed, maps positions in the whitespace free operation back to the file
+struct SourceLine<'a> {
+    file: &'a str,
+    line: usize,
+    text: &'a str,
+    //Column of every byte of the whitespace free operation
+    columns: Vec<usize>,
+}
+
+impl SourceLine<'_> {
+    //Error covering the whitespace free operation between start and end
+    fn error(&self, start: usize, end: usize, message: &str, hint: &'static str) -> ParseError {
+        let last_column = self.columns.last().map_or(1, |c| c + 1);
+        let column = self.columns.get(start).copied().unwrap_or(last_column);
+        let end_column = if end > start { self.columns[end - 1] } else { column - 1 };
+        self.column_error(column, end_column, message, hint)
+    }
+
+    //Error covering the source line from column to end_column
+    fn column_error(&self, column: usize, end_column: usize, message: &str, hint: &'static str) -> ParseError {
+        let text: String = self.text.chars().skip(column - 1).take(end_column + 1 - column).collect();
+
+        ParseError {
+            file: String::from(self.file),
+            line: self.line as u32,
+            column: column as u32,
+            text,
+            source_line: String::from(self.text),
+            message: String::from(message),
+            hint,
+        }
+    }
+}
+
+//Words starting a declaration line, they can't name a variable
+const KEYWORDS: [&str; 2] = ["public", "private"];
+
+fn reserved_keyword_error(src: &SourceLine, start: usize, name: &str) -> ParseError {
+    src.error(start, start + name.len(), &format!("`{}` is a reserved keyword", name), "`public` and `private` only start declarations, rename the variable")
+}
+
+//Variable name: [A-Za-z_][A-Za-z0-9_]*
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+//Numeric literal: 3, 0x1f, [3] or [0x1f], sqbracket marks a negative coefficient
+fn is_number(text: &str) -> bool {
+    let digits = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')).unwrap_or(text);
+    let digits = if text.starts_with('[') { digits.strip_prefix('-').unwrap_or(digits) } else { digits };
+    match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+    }
+}
+
+//Decimal or `0x` hexadecimal integer with an optional leading `-`, returns the sign and magnitude
+fn parse_integer(literal: &str) -> Option<(bool, BigUint)> {
+    let (negative, digits) = match literal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, literal),
+    };
+    let value = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) if !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()) => BigUint::parse_bytes(hex.as_bytes(), 16)?,
+        None if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) => BigUint::parse_bytes(digits.as_bytes(), 10)?,
+        _ => return None,
+    };
+    Some((negative, value))
+}
+
+/// Parse a decimal or `0x` hexadecimal literal with an optional leading `-` into a field element
+/// Returns None for malformed literals and values not below the BN254 scalar field modulus
+pub fn parse_field_element(literal: &str) -> Option<Fr> {
+    let (negative, value) = parse_integer(literal)?;
+    if value >= BigUint::from(Fr::MODULUS) {
+        return None;
+    }
+    let value = Fr::from(value);
+    Some(if negative { -value } else { value })
+}
+
+/// Parse a decimal or `0x` hexadecimal integer of any size with an optional leading `-`, reduced into the field
+pub fn parse_field_element_mod_order(literal: &str) -> Option<Fr> {
+    let (negative, value) = parse_integer(literal)?;
+    let value = Fr::from(value);
+    Some(if negative { -value } else { value })
+}
+
+/// Signed decimal form of a field element, values above half the modulus are written as negatives
+pub fn format_field_element(value: Fr) -> String {
+    if value.into_bigint() > Fr::MODULUS_MINUS_ONE_DIV_TWO {
+        String::from("-") + &BigUint::from(-value).to_string()
     } else {
-        return (digits, operand);
+        BigUint::from(value).to_string()
     }
 }
 
-pub fn parse_circuit(file_name:&str) -> Vec<[String; 5]> {
-    let path = Path::new(file_name);
-    let file = File::open(path);
-    let mut contents = String::new();
-    let res = file.expect("Unable to open").read_to_string(&mut contents);
-    let mut shield_brack: bool = false;
-    let mut parsed_operations: Vec<[String; 5]> = Vec::new();
+//Parse a term like 3a, [3]a, 2*x1, 0x1f*y or 5 into (coefficient, variable)
+//Coefficients are a numeric prefix or `*` factors, a term without variable is a constant on the one wire
+fn parse_term(src: &SourceLine, term: &str, offset: usize) -> Result<(Fr, String), ParseError> {
+    let mut coeff: Option<Fr> = None;
+    let mut variable: Option<String> = None;
+    let mut start = 0;
 
-    //Handle error
-    match res {
-        Ok(_) => {
-            println!("Analyzing circuit");
+    for factor in term.split('*') {
+        let factor_offset = offset + start;
+        start += factor.len() + 1;
+
+        if factor.is_empty() {
+            return Err(src.error(factor_offset, factor_offset, "Missing operand", "every operator needs an operand on both sides, Eg: `a + b`"));
         }
-        Err(_) => {
-            panic!("Circuit analysis failed");
+        if factor.contains('(') || factor.contains(')') {
+            let i = factor.find(['(', ')']).unwrap();
+            return Err(src.error(factor_offset + i, factor_offset + i + 1, "Nested parenthesis are not supported", "only a whole operand can be wrapped in parenthesis, Eg: `(a + b) * c == d`"));
+        }
+
+        //Split the numeric prefix from the variable name, a hex literal takes every hex digit
+        let split = if factor.starts_with('[') {
+            factor.find(']').map_or(factor.len(), |i| i + 1)
+        } else if factor.starts_with("0x") || factor.starts_with("0X") {
+            factor[2..].find(|c: char| !c.is_ascii_hexdigit()).map_or(factor.len(), |i| i + 2)
+        } else {
+            factor.find(|c: char| !c.is_ascii_digit()).unwrap_or(factor.len())
+        };
+        let (number, name) = factor.split_at(split);
+
+        if !number.is_empty() {
+            if !is_number(number) {
+                return Err(src.error(factor_offset, factor_offset + number.len(), "Invalid coefficient", "coefficients are decimal or `0x` hex whole numbers, a coefficient in sqbracket is negative, Eg: `3a`, `0x1f*a` or `[3]a`"));
+            }
+            let bracketed = number.strip_prefix('[').and_then(|n| n.strip_suffix(']'));
+            let digits = bracketed.map_or(number, |n| n.strip_prefix('-').unwrap_or(n));
+            let value = match parse_field_element(digits) {
+                Some(value) if bracketed.is_some() => -value,
+                Some(value) => value,
+                None => return Err(src.error(factor_offset, factor_offset + number.len(), "Coefficient out of range", "coefficients must be smaller than the BN254 scalar field modulus")),
+            };
+            coeff = Some(coeff.unwrap_or(Fr::one()) * value);
+        }
+
+        if !name.is_empty() {
+            let name_offset = factor_offset + number.len();
+            if !is_identifier(name) {
+                return Err(src.error(name_offset, name_offset + name.len(), "Invalid variable name", "variable names start with a letter or `_` followed by letters, digits or `_`, Eg: `x1` or `out_3`"));
+            }
+            if KEYWORDS.contains(&name) {
+                return Err(reserved_keyword_error(src, name_offset, name));
+            }
+            if variable.is_some() {
+                return Err(src.error(offset, offset + term.len(), "More than one variable in term", "multiply variables with a separate constraint, Eg: `a * b == c`"));
+            }
+            variable = Some(String::from(name));
         }
     }
 
-    //Remove whitespace
-    let operations: Vec<String> = contents
-        .split("\r\n")
-        .filter(|o| *o != "")
-        .map(|op: &str| op.chars().filter(|c: &char| !c.is_whitespace()).collect())
-        .collect();
+    let coeff = coeff.unwrap_or(Fr::one());
+    let variable = variable.unwrap_or(String::from(ONE_WIRE));
+    Ok((coeff, variable))
+}
 
-    let _supported_operation = ['*', '+', '-', '/'];
+//Find operators outside sqbracket and parenthesis
+fn top_level_operators(expression: &str) -> Vec<(usize, char)> {
     let _supported_operation_string = "*+-/";
+    let mut shield_brack: bool = false;
+    let mut depth = 0;
+
+    expression
+        .char_indices()
+        .filter(|(_, c)| {
+            match *c {
+                '[' => shield_brack = true,
+                ']' => shield_brack = false,
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
 
-    operations.iter().for_each(|op| {
-        let mut sp_idx = None;
-        let idx: usize;
-
-        let op_count = op
-            .chars()
-            .into_iter()
-            .filter(|c| {
-                //Don't count operator inside sqbracket
-                if *c == '[' {
-                    shield_brack = true;
-                } else if *c == ']' {
-                    shield_brack = false;
-                }
+            !shield_brack && depth == 0 && _supported_operation_string.contains(*c)
+        })
+        .collect()
+}
 
-                if !shield_brack {
-                    _supported_operation_string.contains(*c)
-                } else {
-                    return false;
+//Check that every parenthesis and sqbracket is closed
+fn check_balanced(src: &SourceLine, op: &str) -> Result<(), ParseError> {
+    let mut open: Vec<(usize, char)> = Vec::new();
+    for (i, c) in op.char_indices() {
+        match c {
+            '(' | '[' => open.push((i, c)),
+            ')' | ']' => {
+                let expected = if c == ')' { '(' } else { '[' };
+                match open.pop() {
+                    Some((_, o)) if o == expected => {}
+                    _ => return Err(src.error(i, i + 1, &format!("Unmatched `{}`", c), "remove it or add the opening bracket")),
                 }
-            })
-            .count();
+            }
+            _ => {}
+        }
+    }
+    match open.pop() {
+        Some((i, c)) => Err(src.error(i, i + 1, &format!("Unclosed `{}`", c), "add the closing bracket")),
+        None => Ok(()),
+    }
+}
 
-        // println!("Total operator count in the current operation: {}",op_count);
+//A `*` right after a number is a coefficient factor (3*a), not the product of the constraint
+fn is_coefficient_factor(expression: &str, operators: &[(usize, char)], k: usize) -> bool {
+    let (idx, c) = operators[k];
+    if c != '*' {
+        return false;
+    }
+    let start = if k == 0 { 0 } else { operators[k - 1].0 + 1 };
+    let end = operators.get(k + 1).map_or(expression.len(), |(i, _)| *i);
+    //3*-a and 3*(a + b) are products with a signed or parenthesised right operand
+    end > idx + 1 && is_number(&expression[start..idx]) && !expression[idx + 1..end].starts_with('(')
+}
 
-        match op_count {
-            0 => {
-                panic!("ERROR: Missing operand in circuit");
+//Remove the parenthesis around a linear combination, (a+b) -> a+b
+//Returns the inner expression and how far it moved
+fn strip_parenthesis(operand: &str) -> (&str, usize) {
+    if operand.starts_with('(') && operand.ends_with(')') {
+        let inner = &operand[1..operand.len() - 1];
+        //Keep (a)+(b) as it is, the outer parenthesis don't belong together
+        let mut depth = 0;
+        for c in inner.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return (operand, 0),
+                ')' => depth -= 1,
+                _ => {}
             }
-            1 => {
-                //Find operator index
-                for sp in _supported_operation {
-                    sp_idx = op.find(sp);
-                    match sp_idx {
-                        Some(_) => break,
-                        None => {}
-                    }
-                }
+        }
+        return (inner, 1);
+    }
+    (operand, 0)
+}
 
-                match sp_idx {
-                    Some(_idx) => idx = _idx,
-                    None => {
-                        panic!("ERROR: Unsupported operation")
-                    }
-                }
+//Split a linear combination like a+2b-[3]c+5 into signed (coefficient, variable) terms
+//Constant terms are placed on the constant one wire
+fn parse_linear_terms(src: &SourceLine, expression: &str, offset: usize) -> Result<Vec<(Fr, String)>, ParseError> {
+    let (expression, shift) = strip_parenthesis(expression);
+    let offset = offset + shift;
+    let mut terms: Vec<(Fr, String)> = Vec::new();
+    let mut negative = false;
+    let mut start = 0;
+
+    let mut push_term = |term: &str, term_offset: usize, negative: bool| -> Result<(), ParseError> {
+        //A single term of a sum may be wrapped in parenthesis, (a) + (-2b)
+        let (inner, shift) = strip_parenthesis(term);
+        let (term, term_offset, negative) = match inner.strip_prefix('-') {
+            Some(rest) if shift == 1 => (rest, term_offset + 2, !negative),
+            _ => (inner, term_offset + shift, negative),
+        };
+        if shift == 1 && top_level_operators(term).iter().any(|(_, c)| *c == '+' || *c == '-') {
+            return Err(src.error(term_offset - shift, term_offset - shift + 1, "Nested parenthesis are not supported", "only a whole operand can be wrapped in parenthesis, Eg: `(a + b) * c == d`"));
+        }
+        let (coeff, operand) = parse_term(src, term, term_offset)?;
+        let coeff = if negative { -coeff } else { coeff };
+        terms.push((coeff, operand));
+        Ok(())
+    };
 
-                //Use operator index to split to 3 parts ie left operand,right operand and result
-                let (leftoperand, r) = op.split_at(idx);
-                let right = &r[1..];
-                let parts: Vec<&str> = right.split("==").collect();
-                let rightoperand = parts[0];
-                let output = parts[parts.len() - 1];
+    let operators = top_level_operators(expression);
+    for (k, &(i, c)) in operators.iter().enumerate() {
+        if is_coefficient_factor(expression, &operators, k) {
+            continue;
+        }
+        if c == '*' || c == '/' {
+            return Err(src.error(offset + i, offset + i + 1, "More than one operation in constraint", "each constraint is a single product of two linear combinations, split it into several constraints"));
+        }
+        //Leading sign belongs to the first term
+        if i != 0 {
+            push_term(&expression[start..i], offset + start, negative)?;
+        }
+        negative = c == '-';
+        start = i + 1;
+    }
+    push_term(&expression[start..], offset + start, negative)?;
 
-                let (lcoeff, lop) = shield_brack_parser(leftoperand);
-                let (rcoeff, rop) = shield_brack_parser(rightoperand);
+    Ok(terms)
+}
 
-                //Check if any operand is empty
-                if lop.is_empty() || rop.is_empty() || output.is_empty() {
-                    panic!("ERROR: Invalid operands or output in circuit")
-                }
+//Remove a trailing // or # line comment
+fn strip_comment(line: &str) -> &str {
+    let end = [line.find("//"), line.find('#')].into_iter().flatten().min();
+    match end {
+        Some(end) => &line[..end],
+        None => line,
+    }
+}
 
-                let val_vec: [String; 5] = [lcoeff, lop, rcoeff, rop, String::from(output)];
-                // println!("Val Vec: {:?}", val_vec);
+//Parse the variable list of a `public a, b` or `private c` declaration
+fn parse_declaration(src: &SourceLine, names: &str, offset: usize, is_public: bool, declarations: &mut Vec<(String, bool, ParseError)>) -> Result<(), ParseError> {
+    let mut start = offset;
+    for name in names.split(',') {
+        let name_offset = start;
+        start += name.len() + 1;
 
-                //Borrow value
-                parsed_operations.push(val_vec);
+        if name.is_empty() {
+            return Err(src.error(name_offset, name_offset, "Missing variable name", "list the variables separated by commas, Eg: `public a, b`"));
+        }
+        if !is_identifier(name) {
+            return Err(src.error(name_offset, name_offset + name.len(), "Invalid variable name", "variable names start with a letter or `_` followed by letters, digits or `_`, Eg: `x1` or `out_3`"));
+        }
+        if KEYWORDS.contains(&name) {
+            return Err(reserved_keyword_error(src, name_offset, name));
+        }
+        if name == ONE_WIRE {
+            return Err(src.error(name_offset, name_offset + name.len(), "The constant one wire can't be declared", "`one` is always public with value 1"));
+        }
+        if declarations.iter().any(|(n, p, _)| n == name && *p != is_public) {
+            return Err(src.error(name_offset, name_offset + name.len(), "Variable declared both public and private", "keep a single declaration for every variable"));
+        }
+
+        //Reported after parsing if the variable is never used
+        let unknown = src.error(name_offset, name_offset + name.len(), &format!("Unknown variable `{}`", name), "declared variables must appear in a constraint");
+        declarations.push((String::from(name), is_public, unknown));
+    }
+    Ok(())
+}
+
+//Parse a single whitespace free operation
+fn parse_operation(src: &SourceLine, op: &str, parsed_operations: &mut Vec<Operation>, checked_divisors: &mut Vec<String>) -> Result<(), ParseError> {
+    //Reject characters that can't be part of a constraint
+    if let Some((i, c)) = op.char_indices().find(|(_, c)| !c.is_ascii_alphanumeric() && !"_*+-/=()[]".contains(*c)) {
+        return Err(src.error(i, i + c.len_utf8(), &format!("Unexpected character `{}`", c), "constraints are made of operands, `*`, `/`, `+`, `-`, parenthesis and `==`"));
+    }
+    check_balanced(src, op)?;
+
+    //Split to expression and result
+    let parts: Vec<&str> = op.split("==").collect();
+    if parts.len() == 1 {
+        return Err(src.error(0, op.len(), "Missing `==` in constraint", "write the constraint as `left * right == output`"));
+    }
+    if parts.len() > 2 {
+        let second = parts[0].len() + 2 + parts[1].len();
+        return Err(src.error(second, second + 2, "More than one `==` in constraint", "write the constraint as `left * right == output`"));
+    }
+    let expression = parts[0];
+    let output = parts[1];
+    let output_offset = expression.len() + 2;
+
+    if expression.is_empty() {
+        return Err(src.error(0, 2, "Missing operands before `==`", "write the constraint as `left * right == output`"));
+    }
+    if output.is_empty() {
+        return Err(src.error(expression.len(), output_offset, "Missing output after `==`", "write the constraint as `left * right == output`"));
+    }
+
+    let output_terms = parse_linear_terms(src, output, output_offset)?;
+
+    //Collect operators, don't count operator inside sqbracket or parenthesis
+    let operators = top_level_operators(expression);
+    let product_operators: Vec<&(usize, char)> = operators
+        .iter()
+        .enumerate()
+        .filter(|&(k, (_, c))| (*c == '*' || *c == '/') && !is_coefficient_factor(expression, &operators, k))
+        .map(|(_, operator)| operator)
+        .collect();
+    //Sum or difference of products, a leading sign belongs to the first operand
+    //and a sign right after `*` or `/` to the right operand, a * -b
+    let sum_operator = operators.iter().find(|(i, c)| *i > 0 && (*c == '+' || *c == '-') && !expression[..*i].ends_with(['*', '/']));
+
+    // println!("Total operator count in the current operation: {}",operators.len());
+
+    if product_operators.is_empty() {
+        //(a + b) * one == c, a plain `a == 5` is a * one == 5
+        let left = parse_linear_terms(src, expression, 0)?;
+        let right = vec![(Fr::one(), String::from(ONE_WIRE))];
+
+        parsed_operations.push(Operation { left, right, output: output_terms });
+    } else if product_operators.len() == 1 && sum_operator.is_none() {
+        //Use operator index to split to left operand and right operand
+        let (idx, operator) = *product_operators[0];
+        let (leftoperand, r) = expression.split_at(idx);
+        let rightoperand = &r[1..];
+
+        let left = parse_linear_terms(src, leftoperand, 0)?;
+        let right = parse_linear_terms(src, rightoperand, idx + 1)?;
+
+        //Constant divisors are known to be non zero at compile time
+        let constant_divisor = right.iter().all(|(_, variable)| variable == ONE_WIRE);
+        if operator == '/' && constant_divisor && right.iter().map(|(coeff, _)| *coeff).sum::<Fr>().is_zero() {
+            return Err(src.error(idx + 1, expression.len(), "Division by zero", "the divisor is the constant zero"));
+        }
+
+        if operator == '*' {
+            parsed_operations.push(Operation { left, right, output: output_terms });
+        } else {
+            //a / b == c becomes c * b == a
+            parsed_operations.push(Operation {
+                left: output_terms,
+                right: right.clone(),
+                output: left,
+            });
+
+            if constant_divisor {
+                return Ok(());
             }
-            _ => {
-                panic!("ERROR: More than one operand in circuit");
+
+            //b * b^-1 == one ensures the divisor is non zero
+            let (divisor, _) = strip_parenthesis(rightoperand);
+            let divisor = if right.len() > 1 || divisor.starts_with('-') { format!("({})", divisor) } else { String::from(divisor) };
+            let divisor_inverse = inverse_wire(&divisor);
+            if !checked_divisors.contains(&divisor_inverse) {
+                parsed_operations.push(Operation {
+                    left: right,
+                    right: vec![(Fr::one(), divisor_inverse.clone())],
+                    output: vec![(Fr::one(), String::from(ONE_WIRE))],
+                });
+                checked_divisors.push(divisor_inverse);
             }
         }
-    });
+    } else {
+        //Point at the operator that doesn't fit in a single product
+        let (idx, c) = if product_operators.len() > 1 { *product_operators[1] } else { *sum_operator.unwrap() };
+        return Err(src.error(idx, idx + c.len_utf8(), "More than one operation in constraint", "each constraint is a single product of two linear combinations, wrap sums in parenthesis, Eg: `(a + b) * c == d`"));
+    }
 
-    println!("Circuit analyzed");
-    return parsed_operations;
+    Ok(())
 }
 
-pub fn compute_op_points(parsed_operations: Vec<[String; 5]>, op_type: i32) -> (Vec<Vec<[i32; 2]>>,Vec<String>) {
-    let mut op_points_list: Vec<Vec<[i32; 2]>> = Vec::new();
+pub fn parse_circuit(file_name:&str) -> Result<Circuit, ParseError> {
+    let mut contents = String::new();
 
-    let offset = if op_type == 0 {
-        0
-    } else if op_type == 1 {
-        2
-    } else {
-        4
-    };
+    //Handle error
+    let res = File::open(Path::new(file_name)).and_then(|mut file| file.read_to_string(&mut contents));
+    if let Err(err) = res {
+        return Err(ParseError {
+            file: String::from(file_name),
+            line: 0,
+            column: 0,
+            text: String::new(),
+            source_line: String::new(),
+            message: format!("Unable to read circuit: {}", err),
+            hint: "check that the circuit file exists and is readable",
+        });
+    }
 
-    // Variable ocurrance map
-    let mut occurance_list:Vec<String> = Vec::new();
-    for array in &parsed_operations{
-        let mut exist = false;
-        // println!("Occurance list currently: {:?}",&occurance_list);
-        for op_var in &occurance_list{
-            
-            if op_type == 2{
-
-                 if op_var == &array[offset] {
-                     exist = true;
-                 }
-            }else {
-                if op_var == &array[offset+1]{
-                    exist = true;
+    parse_circuit_str(file_name, &contents)
+}
+
+/// Parse the text of a circuit, errors refer to file_name
+pub fn parse_circuit_str(file_name:&str, contents:&str) -> Result<Circuit, ParseError> {
+    let mut parsed_operations: Vec<Operation> = Vec::new();
+    let mut checked_divisors: Vec<String> = Vec::new();
+    let mut declarations: Vec<(String, bool, ParseError)> = Vec::new();
+    let mut lines: Vec<usize> = Vec::new();
+
+    //Split lines (LF or CRLF), drop comments and remove whitespace
+    for (i, line) in contents.lines().enumerate() {
+        let text = strip_comment(line);
+
+        //public a, b / private c, a line with `==` is a constraint
+        let keyword = KEYWORDS.into_iter().filter(|_| !text.contains("==")).find(|keyword| {
+            text.trim_start().strip_prefix(keyword).is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        });
+
+        let mut op = String::new();
+        let mut columns: Vec<usize> = Vec::new();
+        //Columns of the whitespace after a name or a number, it can't be followed by another one
+        let mut gap: Option<(usize, usize)> = None;
+        let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        for (column, c) in text.chars().enumerate() {
+            if c.is_whitespace() {
+                if op.ends_with(is_word) && Some(op.as_str()) != keyword {
+                    gap = Some(gap.map_or((column + 1, column + 1), |(start, _)| (start, column + 1)));
                 }
+                continue;
             }
+            if let Some((start, end)) = gap.take() {
+                if is_word(c) {
+                    let src = SourceLine { file: file_name, line: i + 1, text: line, columns };
+                    return Err(src.column_error(start, end, "Missing operator between operands", "names and numbers can't contain spaces, join operands with an operator, Eg: `a * b` or `3a`"));
+                }
+            }
+            op.push(c);
+            columns.extend(std::iter::repeat_n(column + 1, c.len_utf8()));
+        }
+        if op.is_empty() {
+            continue;
+        }
+
+        let src = SourceLine { file: file_name, line: i + 1, text: line, columns };
+        match keyword {
+            Some(keyword) => parse_declaration(&src, &op[keyword.len()..], keyword.len(), keyword == "public", &mut declarations)?,
+            None => {
+                parse_operation(&src, &op, &mut parsed_operations, &mut checked_divisors)?;
+                lines.resize(parsed_operations.len(), i + 1);
+            }
+        }
+    }
+
+    //Public wires of the statement, variables are private unless declared public
+    let mut public_variables: Vec<String> = vec![String::from(ONE_WIRE)];
+    for (name, is_public, unknown) in declarations {
+        let is_used = parsed_operations.iter().any(|operation| {
+            [&operation.left, &operation.right, &operation.output].iter().any(|terms| terms.iter().any(|(_, v)| *v == name))
+        });
+        if !is_used {
+            return Err(unknown);
+        }
+        if is_public && !public_variables.contains(&name) {
+            public_variables.push(name);
         }
-        if !exist {
-            if op_type == 2{
-                occurance_list.push(array[offset].clone());
-            }else{
-                occurance_list.push(array[offset+1].clone());
+    }
+
+    Ok(Circuit { operations: parsed_operations, public_variables, lines })
+}
+
+/// Global wire table: the constant one wire followed by every variable of the circuit in order of first appearance, shared by the left, right and output polynomials
+pub fn compute_wire_list(parsed_operations: &[Operation]) -> Vec<String> {
+    let mut wire_list:Vec<String> = vec![String::from(ONE_WIRE)];
+    let mut seen:HashSet<&str> = HashSet::from([ONE_WIRE]);
+    for operation in parsed_operations{
+        for (_, op_var) in operation.left.iter().chain(operation.right.iter()).chain(operation.output.iter()){
+            if seen.insert(op_var) {
+                wire_list.push(op_var.clone());
             }
         }
     }
+    wire_list
+}
 
-    // println!("Occurance list: {:?}",occurance_list);
+pub fn compute_op_points(parsed_operations: Vec<Operation>, op_type: i32) -> (Vec<Vec<[Fr; 2]>>,Vec<String>) {
+    let mut op_points_list: Vec<Vec<[Fr; 2]>> = Vec::new();
 
-    let mut x_index:i32 = 1;
+    let operand = |operation: &Operation| -> Vec<(Fr, String)> {
+        if op_type == 0 {
+            operation.left.clone()
+        } else if op_type == 1 {
+            operation.right.clone()
+        } else {
+            operation.output.clone()
+        }
+    };
 
-    for oc_var in &occurance_list{
-        let mut inner_vec: Vec<[i32; 2]> = Vec::new();
+    //Every wire gets a polynomial in every operand, zero where it doesn't appear
+    let wire_list = compute_wire_list(&parsed_operations);
 
-        for array in &parsed_operations{
+    for wire in &wire_list{
+        let mut inner_vec: Vec<[Fr; 2]> = Vec::new();
 
-            let coeff: &str = if &array[0 + offset] == "" || op_type == 2 {
-                "1"
-            } else {
-                &array[0 + offset]
-            }; 
+        for (i, operation) in parsed_operations.iter().enumerate(){
+            let x = Fr::from(i as u64 + 1);
 
-    
-            let op_var:&String = if op_type == 2 {
-                &array[offset]
-            } else {
-                &array[offset+1]
-            };
-    
-            let x: i32 = x_index;
-            let y:i32 = coeff.parse().expect("Not a valid number");
+            //Sum the coefficients of the variable in this operand
+            let y: Fr = operand(operation)
+                .iter()
+                .filter(|(_, op_var)| op_var == wire)
+                .map(|(coeff, _)| *coeff)
+                .sum();
 
             // println!("[X,Y]: [{:?},{:?}]",x,y);
-            if oc_var == op_var{
-                inner_vec.push([x,y]);
-                
-            } else {
-                inner_vec.push([x,0]);
-            }
-            x_index = x_index + 1;
+            inner_vec.push([x,y]);
         }
 
         //Add inner vec to op list
-        op_points_list.push(inner_vec.clone());
-        inner_vec.clear();
-
-        //Reset x for another occurance
-        x_index = 1;
+        op_points_list.push(inner_vec);
     }
     // println!("Operand points: {:?}", op_points_list);
-    return (op_points_list,occurance_list);
+    (op_points_list,wire_list)
 }
 
-pub fn compute_op_polynomial(op_points: Vec<Vec<[i32; 2]>>) ->(Vec<DensePolynomial<Fr>>,DensePolynomial<Fr>) {
+pub fn compute_op_polynomial(op_points: Vec<Vec<[Fr; 2]>>) ->(Vec<DensePolynomial<Fr>>,DensePolynomial<Fr>) {
 
     let mut polynomial_array:Vec<DensePolynomial<Fr>> = Vec::new();
     let mut final_polynomial:DensePolynomial<Fr> = DensePolynomial::from_coefficients_vec(vec![Fr::zero()]);
 
     for points in &op_points{
         //First variable polynomial
-        let mut x_point_list:Vec<i32> = Vec::new();
-        let mut y_point_list:Vec<i32> = Vec::new();
+        let mut x_point_list:Vec<Fr> = Vec::new();
+        let mut y_point_list:Vec<Fr> = Vec::new();
     
         //Seperate x and y point list
         for point in points{
@@ -254,12 +789,9 @@ pub fn compute_op_polynomial(op_points: Vec<Vec<[i32; 2]>>) ->(Vec<DensePolynomi
             
         }
 
-        let x_point_list_u64:Vec<u64> = x_point_list.iter().map(|&p| p as u64).collect();
-        let y_point_list_u64:Vec<u64>= y_point_list.iter().map(|&p| p as u64).collect();
-
-        let pair_point_list: Vec<(Fr, Fr)> = x_point_list_u64.iter()
-        .zip(y_point_list_u64.iter())
-        .map(|(&x, &y)| (Fr::from(x), Fr::from(y)))
+        let pair_point_list: Vec<(Fr, Fr)> = x_point_list.iter()
+        .zip(y_point_list.iter())
+        .map(|(&x, &y)| (x, y))
         .collect();
 
         //Interpolate polynomial from those points
@@ -271,7 +803,7 @@ pub fn compute_op_polynomial(op_points: Vec<Vec<[i32; 2]>>) ->(Vec<DensePolynomi
     for poly in &polynomial_array{
         final_polynomial = &final_polynomial + poly;
     }
-    return (polynomial_array,final_polynomial);
+    (polynomial_array,final_polynomial)
 
 
 }
@@ -285,7 +817,7 @@ pub fn compute_vanishing_polynomial(length:usize) -> DensePolynomial<Fr> {
         if count == length {
             break;
         }
-        vanishing_index = vanishing_index + 1;
+        vanishing_index += 1;
 
         //Subtract 0 with vanishing index field we get its negative under the prime field
         let neg_vanishing_index_in_prime_field = Fr::zero() - Fr::from(vanishing_index);
@@ -305,11 +837,123 @@ pub fn compute_vanishing_polynomial(length:usize) -> DensePolynomial<Fr> {
     
 }
 
+//Known part of a linear combination and the coefficients of its unknown variables
+fn split_known(terms: &[(Fr, String)], witness_values: &HashMap<String, Fr>) -> (Fr, Vec<(String, Fr)>) {
+    let mut known = Fr::zero();
+    let mut unknown: Vec<(String, Fr)> = Vec::new();
+    for (coeff, variable) in terms {
+        match witness_values.get(variable) {
+            Some(value) => known += *coeff * value,
+            None => match unknown.iter_mut().find(|(v, _)| v == variable) {
+                Some((_, total)) => *total += coeff,
+                None => unknown.push((variable.clone(), *coeff)),
+            },
+        }
+    }
+    //a - a doesn't depend on a
+    unknown.retain(|(_, coeff)| !coeff.is_zero());
+    (known, unknown)
+}
+
+/// Fill in every variable of the circuit from the given inputs by solving the constraints gate by gate
+/// A constraint is solved once a single variable in one operand is unknown, it is revisited when one of its variables gets a value
+pub fn solve_witness(parsed_operations: &[Operation], witness_values: &mut HashMap<String, Fr>) -> Result<(), String> {
+    //Constraints using every variable
+    let mut uses: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, operation) in parsed_operations.iter().enumerate() {
+        for (_, variable) in operation.left.iter().chain(operation.right.iter()).chain(operation.output.iter()) {
+            let constraints = uses.entry(variable.as_str()).or_default();
+            if constraints.last() != Some(&i) {
+                constraints.push(i);
+            }
+        }
+    }
+
+    //Constraints that may have become solvable, in circuit order to start with
+    let mut worklist: VecDeque<usize> = (0..parsed_operations.len()).collect();
+    let mut queued = vec![true; parsed_operations.len()];
+    while let Some(i) = worklist.pop_front() {
+        queued[i] = false;
+        let operation = &parsed_operations[i];
+        let sides = [
+            split_known(&operation.left, witness_values),
+            split_known(&operation.right, witness_values),
+            split_known(&operation.output, witness_values),
+        ];
+        let unknown: Vec<(usize, &(String, Fr))> = sides.iter().enumerate().flat_map(|(k, (_, u))| u.iter().map(move |u| (k, u))).collect();
+
+        //Exactly one unknown variable, linear in the constraint
+        let (side, (variable, coeff)) = match unknown.as_slice() {
+            [(side, unknown)] => (*side, *unknown),
+            _ => continue,
+        };
+        let (left, right, output) = (sides[0].0, sides[1].0, sides[2].0);
+
+        //Value the operand holding the variable must take
+        let target = match side {
+            2 => left * right,
+            _ => {
+                let other = if side == 0 { right } else { left };
+                match other.inverse() {
+                    Some(inverse) => output * inverse,
+                    None if output.is_zero() => continue, //Any value satisfies 0 * x == 0
+                    None => return Err(format!("Division by zero solving {:?} in constraint {}", variable, i + 1)),
+                }
+            }
+        };
+        let value = (target - sides[side].0) * coeff.inverse().unwrap();
+        witness_values.insert(variable.clone(), value);
+        for &j in &uses[variable.as_str()] {
+            if !queued[j] {
+                queued[j] = true;
+                worklist.push_back(j);
+            }
+        }
+    }
+
+    //Everything must be known and satisfied
+    for (i, operation) in parsed_operations.iter().enumerate() {
+        let (left, left_unknown) = split_known(&operation.left, witness_values);
+        let (right, right_unknown) = split_known(&operation.right, witness_values);
+        let (output, output_unknown) = split_known(&operation.output, witness_values);
+        let unknown: Vec<String> = left_unknown.into_iter().chain(right_unknown).chain(output_unknown).map(|(v, _)| v).collect();
+        if !unknown.is_empty() {
+            return Err(format!("Can't solve {:?} in constraint {}, provide them as inputs", unknown, i + 1));
+        }
+        if left * right != output {
+            return Err(format!("Constraint {} is not satisfied by the inputs", i + 1));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ark_bn254::Fr;
     use ark_poly::Polynomial;
+    use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, Compress};
+    use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+    use std::str::FromStr;
+
+    //Interpolation points at x = 1..n
+    fn points(ys: &[i64]) -> Vec<[Fr; 2]> {
+        ys.iter().enumerate().map(|(i, &y)| [Fr::from(i as u64 + 1), Fr::from(y)]).collect()
+    }
+
+    //Circuit parsed from its text, errors name circuit.pika
+    fn parse(text: &str) -> Result<Circuit, ParseError> {
+        parse_circuit_str("circuit.pika", text)
+    }
+
+    fn term(coeff: i64, var: &str) -> (Fr, String) {
+        (Fr::from(coeff), String::from(var))
+    }
+
+    //Witness or public input values by variable name
+    fn values(values: &[(&str, u64)]) -> HashMap<String, Fr> {
+        values.iter().map(|(v, x)| (String::from(*v), Fr::from(*x))).collect()
+    }
 
     #[test]
     fn lagrange_interpolation_test() {
@@ -364,4 +1008,636 @@ mod tests {
         }
         println!("All points verified successfully!");
     }
+
+    #[test]
+    fn linear_constraint_test() {
+        let parsed_operations = parse("a + 2b - [3]c == r1\r\n3a * 2b == r2\r\n").unwrap().operations;
+
+        //Addition and subtraction are multiplied by the constant one wire
+        assert_eq!(parsed_operations[0].left, vec![term(1, "a"), term(2, "b"), term(3, "c")]);
+        assert_eq!(parsed_operations[0].right, vec![term(1, ONE_WIRE)]);
+        assert_eq!(parsed_operations[0].output, vec![term(1, "r1")]);
+
+        let (left_op_points, wire_list) = compute_op_points(parsed_operations, 0);
+        assert_eq!(wire_list, vec![ONE_WIRE, "a", "b", "c", "r1", "r2"]);
+        assert_eq!(left_op_points[0], points(&[0, 0]));
+        assert_eq!(left_op_points[1], points(&[1, 3]));
+        assert_eq!(left_op_points[2], points(&[2, 0]));
+        assert_eq!(left_op_points[3], points(&[3, 0]));
+    }
+
+    #[test]
+    fn wire_list_test() {
+        let parsed_operations = parse("a * b == c\nc * d == e\n").unwrap().operations;
+
+        //Output of the first gate and input of the second share a single wire
+        let (left_op_points, wire_list) = compute_op_points(parsed_operations.clone(), 0);
+        let (right_op_points, right_wire_list) = compute_op_points(parsed_operations.clone(), 1);
+        let (output_op_points, output_wire_list) = compute_op_points(parsed_operations, 2);
+        assert_eq!(wire_list, vec![ONE_WIRE, "a", "b", "c", "d", "e"]);
+        assert_eq!(right_wire_list, wire_list);
+        assert_eq!(output_wire_list, wire_list);
+
+        assert_eq!(left_op_points[3], points(&[0, 1]));
+        assert_eq!(right_op_points[3], points(&[0, 0]));
+        assert_eq!(output_op_points[3], points(&[1, 0]));
+    }
+
+    #[test]
+    fn constant_test() {
+        let parsed_operations = parse("x * x == 9\na * 5 == b\na * b == c + 1\na == 5\n").unwrap().operations;
+
+        assert_eq!(parsed_operations[0].output, vec![term(9, ONE_WIRE)]);
+        assert_eq!(parsed_operations[1].right, vec![term(5, ONE_WIRE)]);
+        assert_eq!(parsed_operations[2].output, vec![term(1, "c"), term(1, ONE_WIRE)]);
+        assert_eq!(parsed_operations[3], Operation {
+            left: vec![term(1, "a")],
+            right: vec![term(1, ONE_WIRE)],
+            output: vec![term(5, ONE_WIRE)],
+        });
+
+        //The one wire carries the constants at wire index 0
+        let (output_op_points, wire_list) = compute_op_points(parsed_operations, 2);
+        assert_eq!(wire_list[0], ONE_WIRE);
+        assert_eq!(output_op_points[0], points(&[9, 0, 1, 5]));
+    }
+
+    #[test]
+    fn division_constraint_test() {
+        let parsed_operations = parse("a / b == c\r\nd / b == e\r\n").unwrap().operations;
+
+        //c * b == a followed by a single non-zero check on b
+        assert_eq!(parsed_operations.len(), 3);
+        assert_eq!(parsed_operations[0], Operation {
+            left: vec![term(1, "c")],
+            right: vec![term(1, "b")],
+            output: vec![term(1, "a")],
+        });
+        assert_eq!(parsed_operations[1], Operation {
+            left: vec![term(1, "b")],
+            right: vec![term(1, &inverse_wire("b"))],
+            output: vec![term(1, ONE_WIRE)],
+        });
+        assert_eq!(parsed_operations[2].output, vec![term(1, "d")]);
+
+        //Constant divisors need no inverse wire, a zero one is refused
+        let parsed_operations = parse("a / 4 == c\na / (3 - 1) == d\n").unwrap().operations;
+        assert_eq!(parsed_operations, vec![
+            Operation { left: vec![term(1, "c")], right: vec![term(4, ONE_WIRE)], output: vec![term(1, "a")] },
+            Operation { left: vec![term(1, "d")], right: vec![term(3, ONE_WIRE), term(-1, ONE_WIRE)], output: vec![term(1, "a")] },
+        ]);
+        let err = parse("a * b == c\na / 0 == c\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 5, "0"));
+        assert_eq!(err.message, "Division by zero");
+        assert_eq!(parse("a / (2 - 2) == c\n").unwrap_err().message, "Division by zero");
+    }
+
+    #[test]
+    fn linear_combination_test() {
+        let parsed_operations = parse("(3a + 2b - c) * (d + 5) == 4e + f\r\n(a + b) / (c - d) == e\r\n").unwrap().operations;
+
+        assert_eq!(parsed_operations[0], Operation {
+            left: vec![term(3, "a"), term(2, "b"), term(-1, "c")],
+            right: vec![term(1, "d"), term(5, ONE_WIRE)],
+            output: vec![term(4, "e"), term(1, "f")],
+        });
+
+        //Division by a linear combination gets its own inverse wire
+        assert_eq!(parsed_operations[1].left, vec![term(1, "e")]);
+        assert_eq!(parsed_operations[1].right, vec![term(1, "c"), term(-1, "d")]);
+        assert_eq!(parsed_operations[1].output, vec![term(1, "a"), term(1, "b")]);
+        assert_eq!(parsed_operations[2].left, vec![term(1, "c"), term(-1, "d")]);
+        assert_eq!(parsed_operations[2].right, vec![term(1, &inverse_wire("(c-d)"))]);
+
+        //A sign after the product operator belongs to the right operand
+        let parsed_operations = parse("a * -b == c\n3 * -a == c\n(a + b) * -2 == c\na / -b == c\n").unwrap().operations;
+        assert_eq!(parsed_operations[0].right, vec![term(-1, "b")]);
+        assert_eq!(parsed_operations[1].left, vec![term(3, ONE_WIRE)]);
+        assert_eq!(parsed_operations[1].right, vec![term(-1, "a")]);
+        assert_eq!(parsed_operations[2].right, vec![term(-2, ONE_WIRE)]);
+        assert_eq!(parsed_operations[4].right, vec![term(1, &inverse_wire("(-b)"))]);
+        assert_eq!(parse("a * -b + c == d\n").unwrap_err().message, "More than one operation in constraint");
+
+        //Parenthesised single terms in a sum
+        let parsed_operations = parse("(a) + (2b) - (-c) == d\n((a) - (b)) * (c) == d\n").unwrap().operations;
+        assert_eq!(parsed_operations[0].left, vec![term(1, "a"), term(2, "b"), term(1, "c")]);
+        assert_eq!(parsed_operations[1].left, vec![term(1, "a"), term(-1, "b")]);
+        assert_eq!(parsed_operations[1].right, vec![term(1, "c")]);
+        let err = parse("(a + b) + c == d\n").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (1, "Nested parenthesis are not supported"));
+    }
+
+    #[test]
+    fn line_endings_and_comments_test() {
+        let parsed_operations = parse("# Sample circuit\n\n3a * 2b == r1 // first gate\r\n   \na * c == r2 # second gate\n// trailing comment").unwrap().operations;
+
+        assert_eq!(parsed_operations.len(), 2);
+        assert_eq!(parsed_operations[0].output, vec![term(1, "r1")]);
+        assert_eq!(parsed_operations[1].output, vec![term(1, "r2")]);
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let err = parse("a * b == c\n\n  d *  == e // missing operand\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.column, 8);
+        assert_eq!(err.text, "");
+        assert_eq!(err.message, "Missing operand");
+
+        let err = parse("a * b == c\r\n3a * 2b == r1 $\r\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 15));
+        assert_eq!(err.text, "$");
+        assert_eq!(err.source_line, "3a * 2b == r1 $");
+
+        //Whitespace separates tokens, it doesn't join them
+        for (circuit, column) in [("a b * c == d\n", 2), ("2 3a * b == c\n", 2), ("x  1 * b == c\n", 2), ("a * b == c d\n", 11), ("public r s\nr * s == t\n", 9)] {
+            let err = parse(circuit).unwrap_err();
+            assert_eq!((err.line, err.column, err.message.as_str()), (1, column, "Missing operator between operands"), "{:?}", circuit);
+        }
+        assert_eq!(parse("x  1 * b == c\n").unwrap_err().text, "  ");
+        assert!(parse("  public  r\n3 * r == 2 a\n").is_err());
+        assert!(parse("  public  r\n(a + 2) * [3] b == r\n").is_ok());
+    }
+
+    #[test]
+    fn identifier_test() {
+        let parsed_operations = parse("x1 * y2 == out_3\n3*x_1 * 2y == _z\na * 3 * b == c\n2*3a1 - [2]b == d\n").unwrap().operations;
+
+        assert_eq!(parsed_operations[0], Operation {
+            left: vec![term(1, "x1")],
+            right: vec![term(1, "y2")],
+            output: vec![term(1, "out_3")],
+        });
+
+        //A `*` after a number is a coefficient factor
+        assert_eq!(parsed_operations[1].left, vec![term(3, "x_1")]);
+        assert_eq!(parsed_operations[1].right, vec![term(2, "y")]);
+        assert_eq!(parsed_operations[2].left, vec![term(1, "a")]);
+        assert_eq!(parsed_operations[2].right, vec![term(3, "b")]);
+        assert_eq!(parsed_operations[3].left, vec![term(6, "a1"), term(2, "b")]);
+        assert_eq!(parsed_operations[3].right, vec![term(1, ONE_WIRE)]);
+    }
+
+    #[test]
+    fn public_variables_test() {
+        let circuit = parse("public r, x\nprivate b\n(x + 2) * b == r\n").unwrap();
+        assert_eq!(circuit.public_variables, vec![ONE_WIRE, "r", "x"]);
+        assert!(circuit.is_public("x"));
+        assert!(!circuit.is_public("b"));
+
+        let err = parse("public r, y\na * b == r\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 11));
+        assert_eq!(err.message, "Unknown variable `y`");
+
+        let err = parse("public r\nprivate r\na * b == r\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.message, "Variable declared both public and private");
+
+        //Keywords can't name variables, however the constraint is spaced
+        for (circuit, column) in [("public * b == c\n", 1), ("public*b==c\n", 1), ("a * 2private == c\n", 6), ("public r, private\nr * b == c\n", 11)] {
+            let err = parse(circuit).unwrap_err();
+            assert_eq!((err.line, err.column), (1, column), "{:?}", circuit);
+            assert!(err.message.ends_with("is a reserved keyword"), "{:?}", circuit);
+        }
+        assert!(parse("publicity * b == c\n").is_ok());
+    }
+
+    #[test]
+    fn field_coefficient_test() {
+        let modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        let modulus_minus_one = "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+        assert_eq!(parse_field_element("0x1f"), Some(Fr::from(31)));
+        assert_eq!(parse_field_element("-5"), Some(-Fr::from(5)));
+        assert_eq!(parse_field_element(modulus_minus_one), Some(-Fr::one()));
+        assert_eq!(parse_field_element(modulus), None);
+        assert_eq!(parse_field_element("0x"), None);
+
+        let parsed_operations = parse(&format!("0x10*a + [5]b - 3c == d\nx * {}x == y\n", modulus_minus_one)).unwrap().operations;
+
+        assert_eq!(parsed_operations[0].left, vec![term(16, "a"), term(-5, "b"), term(-3, "c")]);
+        assert_eq!(parsed_operations[1].right, vec![term(-1, "x")]);
+
+        //Negative coefficients are their additive inverse in the field
+        let (left_op_points, _) = compute_op_points(parsed_operations, 0);
+        assert_eq!(left_op_points[2], points(&[-5, 0]));
+
+        let err = parse(&format!("a * {}b == c\n", modulus)).unwrap_err();
+        assert_eq!(err.message, "Coefficient out of range");
+        assert_eq!(err.text, modulus);
+    }
+
+    #[test]
+    fn field_element_mod_order_test() {
+        let modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        assert_eq!(parse_field_element_mod_order(modulus), Some(Fr::zero()));
+        assert_eq!(parse_field_element_mod_order("-1"), Some(-Fr::one()));
+        assert_eq!(parse_field_element_mod_order("0x10000000000000000"), Some(Fr::from(u64::MAX) + Fr::one()));
+        assert_eq!(parse_field_element_mod_order("12a"), None);
+    }
+
+    #[test]
+    fn solve_witness_test() {
+        let parsed_operations = parse("3a * 2b == r1\nr1 - [4] == r2\nr2 / b == q\n").unwrap().operations;
+        let inputs = |inputs: &[(&str, u64)]| -> HashMap<String, Fr> {
+            let mut witness_values = values(inputs);
+            witness_values.insert(String::from(ONE_WIRE), Fr::one());
+            witness_values
+        };
+
+        let mut witness_values = inputs(&[("a", 1), ("b", 2)]);
+        solve_witness(&parsed_operations, &mut witness_values).unwrap();
+        assert_eq!(witness_values["r1"], Fr::from(12));
+        assert_eq!(witness_values["r2"], Fr::from(16));
+        assert_eq!(witness_values["q"], Fr::from(8));
+        assert_eq!(witness_values[&inverse_wire("b")], Fr::from(2).inverse().unwrap());
+
+        let mut witness_values = inputs(&[("a", 1)]);
+        assert_eq!(solve_witness(&parsed_operations, &mut witness_values).unwrap_err(), "Can't solve [\"b\", \"r1\"] in constraint 1, provide them as inputs");
+
+        let mut witness_values = inputs(&[("a", 1), ("b", 0)]);
+        assert!(solve_witness(&parsed_operations, &mut witness_values).unwrap_err().starts_with("Division by zero"));
+
+        let mut witness_values = inputs(&[("a", 1), ("b", 2), ("r1", 13)]);
+        assert_eq!(solve_witness(&parsed_operations, &mut witness_values).unwrap_err(), "Constraint 1 is not satisfied by the inputs");
+
+        //Constraints listed against their dependency order, x{i+1} = x{i} * 2
+        let chain: String = (0..1000).rev().map(|i| format!("x{} * 2 == x{}\n", i, i + 1)).collect();
+        let parsed_operations = parse(&chain).unwrap().operations;
+        let mut witness_values = inputs(&[("x0", 3)]);
+        solve_witness(&parsed_operations, &mut witness_values).unwrap();
+        assert_eq!(witness_values["x1000"], Fr::from(3u64) * Fr::from(2u64).pow([1000]));
+    }
+
+    #[test]
+    fn check_witness_test() {
+        let circuit = parse("3a * 2b == r1\n\n// comment\na / b == q\nq + 1 == r2\n").unwrap();
+        assert_eq!(circuit.lines, vec![1, 4, 4, 5]);
+
+        let mut witness_values = values(&[("a", 2), ("b", 1), ("r1", 13), ("q", 2), ("r2", 3)]);
+        witness_values.insert(String::from(ONE_WIRE), Fr::one());
+        assert_eq!(circuit.check_witness(&witness_values).unwrap_err(), "Variable: \"b^-1\" not found in the witness file");
+
+        witness_values.insert(inverse_wire("b"), Fr::one());
+        let unsatisfied = circuit.check_witness(&witness_values).unwrap();
+        assert_eq!(unsatisfied, vec![UnsatisfiedConstraint { line: 1, left: Fr::from(6), right: Fr::from(2), output: Fr::from(13) }]);
+        assert_eq!(unsatisfied[0].to_string(), "constraint on line 1 is not satisfied: left = 6, right = 2, output = 13 but left * right = 12");
+    }
+
+    #[test]
+    fn setup_prove_verify_test() {
+        let circuit = parse("public x, r\n(x + 2) * b == r\nr / b == y\n").unwrap().compile("circuit.pika", QapDomain::Sequential).unwrap();
+        let mut rng = ark_std::test_rng();
+        let (proving_key, verifying_key) = setup(&circuit, &mut rng);
+        assert_eq!(verifying_key.public_variables, vec![ONE_WIRE, "x", "r"]);
+
+        let witness: Witness = values(&[("x", 3), ("b", 4), ("r", 20), ("y", 5)]);
+        let proof = prove(&proving_key, &circuit, &witness, &mut rng).unwrap();
+
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("x", 3), ("r", 20)])), Ok(()));
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("x", 4), ("r", 20)])), Err(VerificationError::DivisibilityCheckFailed));
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("x", 3)])), Err(VerificationError::MissingPublicInput(String::from("r"))));
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("x", 3), ("r", 20), ("b", 4)])), Err(VerificationError::UnknownPublicInput(String::from("b"))));
+
+        //Tampered proofs name the check they fail
+        let public_inputs = values(&[("x", 3), ("r", 20)]);
+        let mut tampered = proof;
+        tampered.gl_lop_shifted_eval += tampered.gl_lop_eval;
+        assert_eq!(verify(&verifying_key, &tampered, &public_inputs), Err(VerificationError::KnowledgeCheckFailed(Operand::Left)));
+        let mut tampered = proof;
+        tampered.gr2_rop_shifted_eval += tampered.gr2_rop_eval;
+        assert_eq!(verify(&verifying_key, &tampered, &public_inputs), Err(VerificationError::KnowledgeCheckFailed(Operand::Right)));
+        let mut tampered = proof;
+        tampered.go_oop_shifted_eval += tampered.go_oop_eval;
+        assert_eq!(verify(&verifying_key, &tampered, &public_inputs), Err(VerificationError::KnowledgeCheckFailed(Operand::Output)));
+        let mut tampered = proof;
+        tampered.g_z += tampered.g_z;
+        assert_eq!(verify(&verifying_key, &tampered, &public_inputs), Err(VerificationError::ConsistencyCheckFailed));
+        let mut tampered = proof;
+        tampered.g2_h += tampered.g2_h;
+        assert_eq!(verify(&verifying_key, &tampered, &public_inputs), Err(VerificationError::DivisibilityCheckFailed));
+        let mut tampered = proof;
+        tampered.g2_h = ark_bn254::G2Projective::zero();
+        assert!(matches!(verify(&verifying_key, &tampered, &public_inputs), Err(VerificationError::MalformedProof(_))));
+
+        let witness: Witness = values(&[("x", 3), ("b", 4), ("r", 21), ("y", 5)]);
+        match prove(&proving_key, &circuit, &witness, &mut rng) {
+            Err(Error::UnsatisfiedConstraints(unsatisfied)) => assert_eq!(unsatisfied.iter().map(|c| c.line).collect::<Vec<_>>(), vec![2, 3]),
+            other => panic!("Expected unsatisfied constraints, got {:?}", other),
+        }
+        let witness: Witness = values(&[("x", 3), ("b", 0), ("r", 0), ("y", 5)]);
+        assert_eq!(prove(&proving_key, &circuit, &witness, &mut rng).unwrap_err(), Error::DivisionByZero(inverse_wire("b")));
+
+        //Every private wire section must cover every private wire
+        let witness: Witness = values(&[("x", 3), ("b", 4), ("r", 20), ("y", 5)]);
+        let mut truncated = proving_key.clone();
+        truncated.g_beta_operand_poly_eval.pop();
+        assert_eq!(prove(&truncated, &circuit, &witness, &mut rng).unwrap_err(), Error::KeyMismatch(String::from("private wire sections of the key have different lengths [3, 3, 3, 3, 3, 3, 2, 3, 3]")));
+    }
+
+    #[test]
+    fn seeded_proof_test() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let circuit = parse("public r\na * b == r\n").unwrap().compile("circuit.pika", QapDomain::Sequential).unwrap();
+        let (proving_key, verifying_key) = setup(&circuit, &mut ark_std::test_rng());
+        let witness: Witness = values(&[("a", 3), ("b", 4), ("r", 12)]);
+        let seeded_proof = |seed: u64| prove(&proving_key, &circuit, &witness, &mut ChaCha20Rng::seed_from_u64(seed)).unwrap();
+
+        //The blinding factors only depend on the seed
+        let proof = seeded_proof(42);
+        assert_eq!(seeded_proof(42), proof);
+        assert_ne!(seeded_proof(43), proof);
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("r", 12)])), Ok(()));
+    }
+
+    #[test]
+    fn serialization_roundtrip_test() {
+        let circuit = parse("public r\na * b == r\n").unwrap().compile("circuit.pika", QapDomain::Sequential).unwrap();
+        let mut rng = ark_std::test_rng();
+        let (proving_key, verifying_key) = setup(&circuit, &mut rng);
+        let witness: Witness = values(&[("a", 3), ("b", 4), ("r", 12)]);
+        let proof = prove(&proving_key, &circuit, &witness, &mut rng).unwrap();
+
+        let mut bytes = Vec::new();
+        proving_key.serialize_uncompressed(&mut bytes).unwrap();
+        assert_eq!(ProvingKey::deserialize_uncompressed(&bytes[..]).unwrap(), proving_key);
+        let mut bytes = Vec::new();
+        verifying_key.serialize_uncompressed(&mut bytes).unwrap();
+        assert_eq!(VerifyingKey::deserialize_uncompressed(&bytes[..]).unwrap(), verifying_key);
+        let mut bytes = Vec::new();
+        proof.serialize_uncompressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), proof.uncompressed_size());
+        assert_eq!(Proof::deserialize_uncompressed(&bytes[..]).unwrap(), proof);
+
+        //A truncated proof doesn't deserialize
+        assert!(Proof::deserialize_uncompressed(&bytes[..bytes.len() - 1]).is_err());
+
+        //Affine points, compressed to 32 bytes in G1 and 64 bytes in G2
+        let compressed = proof.to_bytes(Compress::Yes);
+        assert_eq!(compressed.len(), 32 + 6 * 32 + 3 * 64);
+        assert_eq!(proof.to_bytes(Compress::No).len(), 32 + 2 * (compressed.len() - 32));
+        assert_eq!(Proof::read_proof(&compressed).unwrap(), proof);
+        assert_eq!(Proof::read_proof(&proof.to_bytes(Compress::No)).unwrap(), proof);
+        assert!(matches!(Proof::read_proof(&compressed[1..]), Err(ProofError::WrongLength(415))));
+    }
+
+    #[test]
+    fn key_file_test() {
+        //More than 255 G1 elements in the proving key
+        let circuit_text: String = (0..12).map(|i| format!("x{} * y{} == z{}\n", i, i, i)).collect();
+        let circuit = parse(&format!("public z0\n{}", circuit_text)).unwrap().compile("circuit.pika", QapDomain::Sequential).unwrap();
+        let mut rng = ark_std::test_rng();
+        let (proving_key, verifying_key) = setup(&circuit, &mut rng);
+        assert!(proving_key.gl_left_operand_poly_eval.len() * 7 + 12 > 255);
+
+        let mut proving_key_file = Vec::new();
+        proving_key.write_key_file(&mut proving_key_file, Compress::Yes).unwrap();
+        assert_eq!(&proving_key_file[..8], &KEY_FILE_MAGIC);
+        assert_eq!(ProvingKey::read_key_file(&proving_key_file[..]).unwrap(), proving_key);
+        let mut verifying_key_file = Vec::new();
+        verifying_key.write_key_file(&mut verifying_key_file, Compress::Yes).unwrap();
+        assert_eq!(VerifyingKey::read_key_file(&verifying_key_file[..]).unwrap(), verifying_key);
+        let mut uncompressed_file = Vec::new();
+        proving_key.write_key_file(&mut uncompressed_file, Compress::No).unwrap();
+        assert!(uncompressed_file.len() > proving_key_file.len() * 3 / 2);
+        assert_eq!(ProvingKey::read_key_file(&uncompressed_file[..]).unwrap(), proving_key);
+
+        //Files of earlier versions carry no fingerprint
+        let mut version_2_file = uncompressed_file.clone();
+        version_2_file[8] = 2;
+        assert!(matches!(ProvingKey::read_key_file(&version_2_file[..]), Err(KeyFileError::Format(FileFormatError::UnsupportedVersion { found: 2, .. }))));
+
+        //Keys of the other kind, other versions, foreign and truncated files are refused
+        assert!(matches!(ProvingKey::read_key_file(&verifying_key_file[..]), Err(KeyFileError::WrongKind { expected: KeyKind::Proving, found: 2 })));
+        let mut newer_version = verifying_key_file.clone();
+        newer_version[8] = 5;
+        assert!(matches!(VerifyingKey::read_key_file(&newer_version[..]), Err(KeyFileError::Format(FileFormatError::UnsupportedVersion { found: 5, .. }))));
+        let mut unknown_encoding = verifying_key_file.clone();
+        unknown_encoding[11] = 7;
+        assert!(matches!(VerifyingKey::read_key_file(&unknown_encoding[..]), Err(KeyFileError::UnknownEncoding(7))));
+        assert!(matches!(VerifyingKey::read_key_file(&b"not a key file"[..]), Err(KeyFileError::Format(FileFormatError::BadMagic { .. }))));
+        assert!(ProvingKey::read_key_file(&proving_key_file[..proving_key_file.len() - 1]).is_err());
+        //Section 3 holds G1 elements, tag it as G2
+        let mut wrong_group = verifying_key_file.clone();
+        let third_section = 8 + 2 + 1 + 1 + 4 + (1 + 8 + 32) + 1 + 8 + verifying_key.public_variables.iter().map(|v| 8 + v.len()).sum::<usize>();
+        wrong_group[third_section] = GroupTag::G2 as u8;
+        assert!(matches!(VerifyingKey::read_key_file(&wrong_group[..]), Err(KeyFileError::WrongGroup { section: 3, expected: GroupTag::G1, found: 2 })));
+    }
+
+    #[test]
+    fn point_validation_test() {
+        use ark_bn254::{Fq, Fq2, G1Affine, G2Affine, G1Projective, G2Projective};
+        use ark_ec::{AffineRepr, CurveGroup};
+
+        let off_curve = G1Projective::from(G1Affine::new_unchecked(Fq::from(1u64), Fq::from(1u64)));
+        //G2 has a cofactor, the first point found from x is outside the subgroup
+        let outside_subgroup = (1u64..).find_map(|x| G2Affine::get_point_from_x_unchecked(Fq2::from(x), false)).unwrap();
+        assert!(outside_subgroup.is_on_curve());
+        let outside_subgroup = G2Projective::from(outside_subgroup);
+
+        assert_eq!(validate_point(&G1Projective::from(G1Affine::generator()), false), Ok(()));
+        assert_eq!(validate_point(&off_curve, true), Err(InvalidPoint::NotOnCurve));
+        assert_eq!(validate_point(&outside_subgroup, true), Err(InvalidPoint::NotInSubgroup));
+        assert_eq!(validate_point(&G2Projective::zero(), true), Ok(()));
+        assert_eq!(validate_point(&G2Projective::zero(), false), Err(InvalidPoint::Identity));
+        assert_eq!(validate_point(&(outside_subgroup.into_affine().clear_cofactor().into_group()), false), Ok(()));
+
+        let circuit = parse("public r\na * b == r\n").unwrap().compile("circuit.pika", QapDomain::Sequential).unwrap();
+        let mut rng = ark_std::test_rng();
+        let (proving_key, verifying_key) = setup(&circuit, &mut rng);
+        let witness: Witness = values(&[("a", 3), ("b", 4), ("r", 12)]);
+        let proof = prove(&proving_key, &circuit, &witness, &mut rng).unwrap();
+
+        //Uncompressed, a compressed point can't be off the curve
+        let proof_bytes = |proof: &Proof| proof.to_bytes(Compress::No);
+        assert_eq!(Proof::read_proof(&proof_bytes(&proof)).unwrap(), proof);
+        let mut tampered = proof;
+        tampered.g2_h = outside_subgroup;
+        assert!(matches!(Proof::read_proof(&proof_bytes(&tampered)), Err(ProofError::InvalidPoint { element: "g2_h", reason: InvalidPoint::NotInSubgroup })));
+        let mut tampered = proof;
+        tampered.gl_lop_eval = off_curve;
+        assert!(matches!(Proof::read_proof(&proof_bytes(&tampered)), Err(ProofError::InvalidPoint { element: "gl_lop_eval", reason: InvalidPoint::NotOnCurve })));
+        let mut tampered = proof;
+        tampered.g_z = G1Projective::zero();
+        assert!(matches!(Proof::read_proof(&proof_bytes(&tampered)), Err(ProofError::InvalidPoint { element: "g_z", reason: InvalidPoint::Identity })));
+        let mut trailing = proof_bytes(&proof);
+        trailing.push(0);
+        assert!(matches!(Proof::read_proof(&trailing), Err(ProofError::WrongLength(801))));
+
+        //The generator can't be the identity, per-wire evaluations can
+        let key_file = |key: &VerifyingKey| { let mut bytes = Vec::new(); key.write_key_file(&mut bytes, Compress::No).unwrap(); bytes };
+        let mut tampered = verifying_key.clone();
+        tampered.g = G1Projective::zero();
+        assert!(matches!(VerifyingKey::read_key_file(&key_file(&tampered)[..]), Err(KeyFileError::InvalidPoint { section: 3, element: 0, reason: InvalidPoint::Identity })));
+        let mut tampered = verifying_key.clone();
+        tampered.gr2_public_right_operand_poly_eval[0] = outside_subgroup;
+        assert!(matches!(VerifyingKey::read_key_file(&key_file(&tampered)[..]), Err(KeyFileError::InvalidPoint { section: 13, element: 0, reason: InvalidPoint::NotInSubgroup })));
+        let mut tampered = verifying_key.clone();
+        tampered.go_public_output_operand_poly_eval[0] = G1Projective::zero();
+        assert_eq!(VerifyingKey::read_key_file(&key_file(&tampered)[..]).unwrap(), tampered);
+    }
+
+    #[test]
+    fn circuit_fingerprint_test() {
+        let fingerprint = |text: &str| -> CircuitFingerprint { parse(text).unwrap().fingerprint() };
+        let circuit_fingerprint = fingerprint("public r\n(a + b) * c == r\n");
+        assert_eq!(circuit_fingerprint.to_string().len(), 64);
+
+        //Formatting, comments, term order and private names don't matter
+        assert_eq!(fingerprint("// sum times c\npublic r\n( a+b )*c==r  # output\n"), circuit_fingerprint);
+        assert_eq!(fingerprint("public r\n(x + y) * z == r\n"), circuit_fingerprint);
+        assert_eq!(fingerprint("public r\n(2a + b - a) * c == r\n"), circuit_fingerprint);
+        //Coefficients, public variables and constraint order do
+        assert_ne!(fingerprint("public r\n(2a + b) * c == r\n"), circuit_fingerprint);
+        assert_ne!(fingerprint("public r, c\n(a + b) * c == r\n"), circuit_fingerprint);
+        assert_ne!(fingerprint("public s\n(a + b) * c == s\n"), circuit_fingerprint);
+        assert_ne!(fingerprint("public r\nc * (a + b) == r\n"), circuit_fingerprint);
+
+        let circuit = parse("public r\na * b == r\n").unwrap().compile("circuit.pika", QapDomain::Sequential).unwrap();
+        let other_circuit = parse("public r\na * 2b == r\n").unwrap().compile("circuit.pika", QapDomain::Sequential).unwrap();
+
+        let mut rng = ark_std::test_rng();
+        let (proving_key, verifying_key) = setup(&circuit, &mut rng);
+        let (other_proving_key, _) = setup(&other_circuit, &mut rng);
+        assert_eq!(proving_key.circuit_fingerprint, circuit.fingerprint);
+        assert_eq!(verifying_key.circuit_fingerprint, circuit.fingerprint);
+
+        //Same shape, so only the fingerprint tells the circuits apart
+        let witness: Witness = values(&[("a", 3), ("b", 4), ("r", 12)]);
+        assert_eq!(prove(&proving_key, &other_circuit, &witness, &mut rng).unwrap_err(), Error::FingerprintMismatch { key: circuit.fingerprint, circuit: other_circuit.fingerprint });
+        let witness: Witness = values(&[("a", 3), ("b", 2), ("r", 12)]);
+        let other_proof = prove(&other_proving_key, &other_circuit, &witness, &mut rng).unwrap();
+        let public_inputs: PublicInputs = values(&[("r", 12)]);
+        assert_eq!(verify(&verifying_key, &other_proof, &public_inputs), Err(VerificationError::FingerprintMismatch { key: circuit.fingerprint, proof: other_circuit.fingerprint }));
+    }
+
+    #[test]
+    fn compiled_circuit_test() {
+        let circuit = parse("public r\n(a + 2) * b == r\nr / b == c\n").unwrap();
+        let compiled = circuit.compile("circuit.pika", QapDomain::Sequential).unwrap();
+        assert_eq!(compiled.fingerprint, circuit.fingerprint());
+        assert_eq!(compiled.wire_list, compute_wire_list(&circuit.operations));
+        assert_eq!(compiled.vanishing_polynomial, compute_vanishing_polynomial(circuit.operations.len()));
+
+        let mut bytes: Vec<u8> = Vec::new();
+        compiled.write_compiled_circuit(&mut bytes).unwrap();
+        assert_eq!(bytes[..8], COMPILED_CIRCUIT_MAGIC);
+        let loaded = CompiledCircuit::read_compiled_circuit(&bytes[..]).unwrap();
+        assert_eq!(loaded, compiled);
+        assert_eq!(loaded.source, "circuit.pika");
+
+        //Keys and proofs from the loaded circuit are the ones of the fresh compilation
+        let (proving_key, verifying_key) = setup(&loaded, &mut ark_std::test_rng());
+        assert_eq!(setup(&compiled, &mut ark_std::test_rng()), (proving_key.clone(), verifying_key.clone()));
+        let witness: Witness = values(&[("a", 1), ("b", 2), ("r", 6), ("c", 3)]);
+        let proof = prove(&proving_key, &loaded, &witness, &mut ark_std::test_rng()).unwrap();
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("r", 6)])), Ok(()));
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 1;
+        assert!(matches!(CompiledCircuit::read_compiled_circuit(&bad_magic[..]), Err(CompiledCircuitError::Format(FileFormatError::BadMagic { .. }))));
+        //Version 1 had no domain, version 2 stored the coefficients as text
+        let mut bad_version = bytes.clone();
+        bad_version[8] = 2;
+        assert!(matches!(CompiledCircuit::read_compiled_circuit(&bad_version[..]), Err(CompiledCircuitError::Format(FileFormatError::UnsupportedVersion { found: 2, .. }))));
+        assert!(matches!(CompiledCircuit::read_compiled_circuit(&bytes[..bytes.len() - 1]), Err(CompiledCircuitError::Format(FileFormatError::Serialization(_)))));
+        //Stale fingerprint, it is the first field after the header
+        let mut bad_fingerprint = bytes.clone();
+        bad_fingerprint[10] ^= 1;
+        assert!(matches!(CompiledCircuit::read_compiled_circuit(&bad_fingerprint[..]), Err(CompiledCircuitError::Inconsistent(_))));
+        //Missing operand polynomial
+        let mut truncated = compiled.clone();
+        if let OperandPolynomials::Sequential { right, .. } = &mut truncated.operand_polynomials {
+            right.pop();
+        }
+        let mut truncated_bytes: Vec<u8> = Vec::new();
+        truncated.write_compiled_circuit(&mut truncated_bytes).unwrap();
+        assert_eq!(CompiledCircuit::read_compiled_circuit(&truncated_bytes[..]).unwrap_err().to_string(), format!("Corrupted compiled circuit: {} operand polynomials for {} wires", compiled.wire_list.len() - 1, compiled.wire_list.len()));
+
+        //Polynomials that don't interpolate the constraints
+        let read_error = |compiled: &CompiledCircuit| {
+            let mut bytes: Vec<u8> = Vec::new();
+            compiled.write_compiled_circuit(&mut bytes).unwrap();
+            CompiledCircuit::read_compiled_circuit(&bytes[..]).unwrap_err().to_string()
+        };
+        let mut tampered = compiled.clone();
+        if let OperandPolynomials::Sequential { output, .. } = &mut tampered.operand_polynomials {
+            output[3].coeffs[0] += Fr::one();
+        }
+        assert_eq!(read_error(&tampered), "Corrupted compiled circuit: operand polynomials don't match the constraints");
+        let mut tampered = compiled.clone();
+        tampered.vanishing_polynomial.coeffs[0] += Fr::one();
+        assert_eq!(read_error(&tampered), "Corrupted compiled circuit: vanishing polynomial doesn't vanish on the constraints");
+        //Public variables must be wires of the constraints, even with a matching fingerprint
+        let mut tampered = compiled.clone();
+        tampered.circuit.public_variables.push(String::from("zz"));
+        tampered.fingerprint = tampered.circuit.fingerprint();
+        assert_eq!(read_error(&tampered), "Corrupted compiled circuit: public variable \"zz\" isn't a distinct wire of the constraints");
+        let (_, verifying_key) = setup(&tampered, &mut ark_std::test_rng());
+        assert_eq!(verifying_key.public_variables.len(), 3);
+        let mut tampered = compiled.clone();
+        tampered.circuit.public_variables.remove(0);
+        tampered.fingerprint = tampered.circuit.fingerprint();
+        assert_eq!(read_error(&tampered), "Corrupted compiled circuit: public variables don't start with the one wire");
+        //A flipped bit in the constant coefficient of the vanishing polynomial, the last field of the file
+        let mut flipped = bytes.clone();
+        flipped[bytes.len() - 4 * 32] ^= 1;
+        assert!(matches!(CompiledCircuit::read_compiled_circuit(&flipped[..]), Err(CompiledCircuitError::Inconsistent(_))));
+    }
+
+    #[test]
+    fn radix2_domain_test() {
+        let circuit = parse("public x, r\n(x + 2) * b == r\nr / b == y\ny * y == z\nz - 3 == w\n").unwrap();
+        assert_eq!(circuit.operations.len(), 5);
+        let compiled = circuit.compile("circuit.pika", QapDomain::Radix2).unwrap();
+        let sequential = circuit.compile("circuit.pika", QapDomain::Sequential).unwrap();
+        assert_eq!(compiled.domain(), QapDomain::Radix2);
+        assert_eq!(QapDomain::from_str("radix2"), Ok(QapDomain::Radix2));
+        assert!(QapDomain::from_str("fft").is_err());
+
+        //Padded to 8 roots of unity, vanishing polynomial x^8 - 1
+        let domain = Radix2EvaluationDomain::<Fr>::new(8).unwrap();
+        assert_eq!(compiled.vanishing_polynomial.degree(), 8);
+        for root in domain.elements() {
+            assert!(compiled.vanishing_polynomial.evaluate(&root).is_zero());
+        }
+
+        //Wire polynomials are the coefficients of the constraints at the roots and zero on the padding
+        let [left, right, output] = compiled.wire_evaluations(&domain.element(1));
+        let [seq_left, seq_right, seq_output] = sequential.wire_evaluations(&Fr::from(2u64));
+        assert_eq!((&left, &right, &output), (&seq_left, &seq_right, &seq_output));
+        let [left, right, output] = compiled.wire_evaluations(&domain.element(6));
+        assert!(left.iter().chain(right.iter()).chain(output.iter()).all(|value| value.is_zero()));
+        //Implicit wire evaluations agree with the interpolated operand polynomials off the domain
+        let s = Fr::from(123456789u64);
+        let wire_values: Vec<Fr> = (0..compiled.wire_list.len() as u64).map(|i| Fr::from(i * i + 7)).collect();
+        let evaluations = compiled.wire_evaluations(&s);
+        for (polynomial, evaluations) in compiled.operand_polynomials(&wire_values).iter().zip(evaluations.iter()) {
+            let expected: Fr = evaluations.iter().zip(wire_values.iter()).map(|(evaluation, value)| *evaluation * value).sum();
+            assert_eq!(polynomial.evaluate(&s), expected);
+        }
+
+        let mut bytes: Vec<u8> = Vec::new();
+        compiled.write_compiled_circuit(&mut bytes).unwrap();
+        assert_eq!(CompiledCircuit::read_compiled_circuit(&bytes[..]).unwrap(), compiled);
+        let mut tampered = compiled.clone();
+        tampered.vanishing_polynomial.coeffs[8] += Fr::one();
+        let mut bytes: Vec<u8> = Vec::new();
+        tampered.write_compiled_circuit(&mut bytes).unwrap();
+        assert!(matches!(CompiledCircuit::read_compiled_circuit(&bytes[..]), Err(CompiledCircuitError::Inconsistent(_))));
+
+        //Keys over one domain don't fit the other
+        assert_ne!(compiled.fingerprint, sequential.fingerprint);
+        assert_eq!(sequential.fingerprint, circuit.fingerprint());
+        let mut rng = ark_std::test_rng();
+        let (proving_key, verifying_key) = setup(&compiled, &mut rng);
+        assert_eq!(proving_key.g2sk.len(), 8);
+
+        let witness: Witness = values(&[("x", 3), ("b", 4), ("r", 20), ("y", 5), ("z", 25), ("w", 22)]);
+        assert_eq!(prove(&proving_key, &sequential, &witness, &mut rng).unwrap_err(), Error::FingerprintMismatch { key: compiled.fingerprint, circuit: sequential.fingerprint });
+        let proof = prove(&proving_key, &compiled, &witness, &mut rng).unwrap();
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("x", 3), ("r", 20)])), Ok(()));
+        assert_eq!(verify(&verifying_key, &proof, &values(&[("x", 4), ("r", 20)])), Err(VerificationError::DivisibilityCheckFailed));
+    }
 }
@@ -1,8 +1,11 @@
 use clap::{Parser, Subcommand}; // Import `clap`
+use pikachu::QapDomain;
 
+mod compile;
 mod trusted_setup;
 mod prover;
 mod verifier;
+mod witness;
 
 /// CLI interface for running different parts of the zkSNARK system
 #[derive(Parser)]
@@ -14,14 +17,93 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Interpolate the circuit polynomials once and write them for the setup and the prover
+    Compile {
+        /// Circuit to compile
+        #[arg(long, value_name = "FILE", default_value = "circuit.pika")]
+        circuit: String,
+        /// Where to write the compiled circuit
+        #[arg(long, value_name = "FILE", default_value = "circuit.pikac")]
+        out: String,
+        /// Interpolation points: sequential (x = 1..n) or radix2 (roots of unity with FFTs, for large circuits)
+        #[arg(long, value_name = "DOMAIN", default_value = "sequential")]
+        domain: QapDomain,
+    },
     /// Run trusted setup
-    TrustedSetup,
+    TrustedSetup {
+        /// Circuit to generate the keys for
+        #[arg(long, value_name = "FILE", default_value = "circuit.pika")]
+        circuit: String,
+        /// Use this compiled circuit instead of compiling --circuit
+        #[arg(long, value_name = "FILE", conflicts_with = "circuit")]
+        compiled: Option<String>,
+        /// Interpolation points when compiling --circuit: sequential or radix2
+        #[arg(long, value_name = "DOMAIN", default_value = "sequential", conflicts_with = "compiled")]
+        domain: QapDomain,
+        /// Where to write the proving key
+        #[arg(long, value_name = "FILE", default_value = "proving_key.bin")]
+        proving_key: String,
+        /// Where to write the verification key
+        #[arg(long, value_name = "FILE", default_value = "verification_key.bin")]
+        verifying_key: String,
+        /// Write uncompressed points, the key files are twice as big but load faster
+        #[arg(long)]
+        uncompressed: bool,
+    },
+    /// Solve the circuit for every variable from the input values and write the witness
+    Witness {
+        /// Input values as a JSON object, Eg: '{"a": "3", "b": "4"}'
+        inputs: String,
+        /// Circuit to solve
+        #[arg(long, value_name = "FILE", default_value = "./src/prover/prover_polynomial.pika")]
+        circuit: String,
+        /// Where to write the witness
+        #[arg(long, value_name = "FILE", default_value = "./src/prover/witness.json")]
+        out: String,
+    },
     /// Run prover
-    Prover,
+    #[command(alias = "prove")]
+    Prover {
+        /// Circuit the witness satisfies, the same as the one of the trusted setup
+        #[arg(long, value_name = "FILE", default_value = "./src/prover/prover_polynomial.pika")]
+        circuit: String,
+        /// Use this compiled circuit instead of compiling --circuit, the one given to the trusted setup
+        #[arg(long, value_name = "FILE", conflicts_with = "circuit")]
+        compiled: Option<String>,
+        /// Interpolation points when compiling --circuit, the domain of the trusted setup: sequential or radix2
+        #[arg(long, value_name = "DOMAIN", default_value = "sequential", conflicts_with = "compiled")]
+        domain: QapDomain,
+        /// Proving key of the circuit
+        #[arg(long, value_name = "FILE", default_value = "proving_key.bin")]
+        proving_key: String,
+        /// Values of the circuit variables as JSON
+        #[arg(long, value_name = "FILE", default_value = "./src/prover/witness.json")]
+        witness: String,
+        /// INSECURE: derive the blinding factors from this seed instead of OS randomness, the proof no longer hides the witness. Only for reproducible test vectors
+        #[arg(long, value_name = "SEED")]
+        deterministic_seed: Option<u64>,
+        /// Write the proof string to this file, - for stdout (progress messages go to stderr)
+        #[arg(short, long, value_name = "FILE", default_value = "-")]
+        output: String,
+        /// Encode the proof with uncompressed points, twice as long but faster to verify
+        #[arg(long)]
+        uncompressed: bool,
+    },
     /// Run verifier with the provided proof
+    #[command(alias = "verify")]
     Verifier {
-        /// Base64-encoded proof string
-        proof: String,
+        /// Base64-encoded proof string, - reads it from stdin
+        #[arg(required_unless_present = "proof_file", conflicts_with = "proof_file")]
+        proof: Option<String>,
+        /// Read the base64-encoded proof from this file, - for stdin
+        #[arg(long, value_name = "FILE")]
+        proof_file: Option<String>,
+        /// Value of a public variable as name=value, repeat for every public variable
+        #[arg(short, long = "public", value_name = "NAME=VALUE")]
+        public: Vec<String>,
+        /// Verification key of the circuit
+        #[arg(long, value_name = "FILE", default_value = "verification_key.bin")]
+        verifying_key: String,
     },
 }
 
@@ -29,8 +111,10 @@ fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::TrustedSetup => trusted_setup::main(),
-        Commands::Prover => prover::main(),
-        Commands::Verifier { proof } => verifier::main(&proof),
+        Commands::Compile { circuit, out, domain } => compile::main(&circuit, &out, domain),
+        Commands::TrustedSetup { circuit, compiled, domain, proving_key, verifying_key, uncompressed } => trusted_setup::main(&compile::load_circuit(&circuit, compiled.as_deref(), domain), &proving_key, &verifying_key, uncompressed),
+        Commands::Witness { inputs, circuit, out } => witness::main(&inputs, &circuit, &out),
+        Commands::Prover { circuit, compiled, domain, proving_key, witness, output, deterministic_seed, uncompressed } => prover::main(&compile::load_circuit(&circuit, compiled.as_deref(), domain), &proving_key, &witness, &output, deterministic_seed, uncompressed),
+        Commands::Verifier { proof, proof_file, public, verifying_key } => verifier::main(proof.as_deref(), proof_file.as_deref(), &public, &verifying_key),
     }
 }
@@ -1,3 +1,4 @@
 // src/prover/mod.rs
+#[allow(clippy::module_inception)]
 mod prover; // this declares the prover.rs file as a submodule
 pub use prover::*; // this re-exports everything from prover.rs
\ No newline at end of file
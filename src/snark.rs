@@ -0,0 +1,664 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Mul;
+use ark_bn254::{Bn254, Fr, G1Projective as G, G2Projective as G2};
+use ark_ec::{AffineRepr, CurveGroup, Group};
+use ark_ec::short_weierstrass::{Projective, SWCurveConfig};
+use ark_ec::pairing::Pairing;
+use ark_ff::{Field, Zero, One};
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial, Polynomial};
+use ark_std::UniformRand;
+use ark_std::rand::Rng;
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, Compress, SerializationError, Validate};
+use crate::{CircuitFingerprint, CompiledCircuit, Operation, UnsatisfiedConstraint, ONE_WIRE, INVERSE_WIRE_SUFFIX};
+
+/// Values of the circuit variables by name, the one wire and inverse wires are filled in by the prover
+pub type Witness = HashMap<String, Fr>;
+
+/// Values of the public variables by name, the one wire is always 1 and must not be given
+pub type PublicInputs = HashMap<String, Fr>;
+
+/// Proving key, the per-wire vectors hold the private wires in wire list order
+/// Serialized field by field in declaration order with `CanonicalSerialize`
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProvingKey {
+    /// Fingerprint of the circuit of the setup
+    pub circuit_fingerprint: CircuitFingerprint,
+    /// gl^li(s) of every private wire
+    pub gl_left_operand_poly_eval: Vec<G>,
+    /// gr^ri(s) of every private wire
+    pub gr_right_operand_poly_eval: Vec<G>,
+    /// go^oi(s) of every private wire
+    pub go_output_operand_poly_eval: Vec<G>,
+    /// gl^alphal*li(s) of every private wire
+    pub gl_alpha_left_operand_poly_eval: Vec<G>,
+    /// gr^alphar*ri(s) of every private wire
+    pub gr_alpha_right_operand_poly_eval: Vec<G>,
+    /// go^alphao*oi(s) of every private wire
+    pub go_alpha_output_operand_poly_eval: Vec<G>,
+    /// gl^beta*li(s) * gr^beta*ri(s) * go^beta*oi(s) of every private wire
+    pub g_beta_operand_poly_eval: Vec<G>,
+    /// gl^t(s)
+    pub gl_t_eval: G,
+    /// gr^t(s)
+    pub gr_t_eval: G,
+    /// go^t(s)
+    pub go_t_eval: G,
+    /// gl^alphal*t(s)
+    pub gl_alphal_t_eval: G,
+    /// gr^alphar*t(s)
+    pub gr_alphar_t_eval: G,
+    /// go^alphao*t(s)
+    pub go_alphao_t_eval: G,
+    /// gl^beta*t(s)
+    pub gl_beta_t_eval: G,
+    /// gr^beta*t(s)
+    pub gr_beta_t_eval: G,
+    /// go^beta*t(s)
+    pub go_beta_t_eval: G,
+    /// g^alphal
+    pub g_alphal: G,
+    /// g^alphar
+    pub g_alphar: G,
+    /// g^alphao
+    pub g_alphao: G,
+    /// gr2^t(s)
+    pub gr2_t_eval: G2,
+    /// gr2^alphar*t(s)
+    pub gr2_alphar_t_eval: G2,
+    /// gr2^ri(s) of every private wire
+    pub gr2_right_operand_poly_eval: Vec<G2>,
+    /// gr2^alphar*ri(s) of every private wire
+    pub gr2_alpha_right_operand_poly_eval: Vec<G2>,
+    /// g2^s^k for 1 <= k <= number of constraints
+    pub g2sk: Vec<G2>,
+}
+
+/// Verifying key, the public vectors follow `public_variables`
+/// Serialized field by field in declaration order with `CanonicalSerialize`
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct VerifyingKey {
+    /// Fingerprint of the circuit of the setup
+    pub circuit_fingerprint: CircuitFingerprint,
+    /// Public variables of the statement, the constant one wire comes first
+    pub public_variables: Vec<String>,
+    /// Generator of G1
+    pub g: G,
+    /// go^t(s)
+    pub go_t_eval: G,
+    /// g^alphar
+    pub g_alphar: G,
+    /// gl^li(s) of every public variable
+    pub gl_public_left_operand_poly_eval: Vec<G>,
+    /// go^oi(s) of every public variable
+    pub go_public_output_operand_poly_eval: Vec<G>,
+    /// Generator of G2
+    pub g2: G2,
+    /// g2^alphal
+    pub g2_alphal: G2,
+    /// g2^alphao
+    pub g2_alphao: G2,
+    /// g2^gamma
+    pub g2_gamma: G2,
+    /// g2^beta*gamma
+    pub g2_beta_gamma: G2,
+    /// gr2^ri(s) of every public variable
+    pub gr2_public_right_operand_poly_eval: Vec<G2>,
+}
+
+/// Proof of the private part of the operand polynomials, the circuit fingerprint followed by 6 G1 elements and 3 G2 elements
+#[derive(Debug, Clone, Copy, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Proof {
+    /// Fingerprint of the circuit the proof is for
+    pub circuit_fingerprint: CircuitFingerprint,
+    /// gl^Lp(s)
+    pub gl_lop_eval: G,
+    /// gr^Rp(s)
+    pub gr_rop_eval: G,
+    /// go^Op(s)
+    pub go_oop_eval: G,
+    /// gl^alphal*Lp(s)
+    pub gl_lop_shifted_eval: G,
+    /// go^alphao*Op(s)
+    pub go_oop_shifted_eval: G,
+    /// g^Z(s), the beta consistency term
+    pub g_z: G,
+    /// gr2^Rp(s)
+    pub gr2_rop_eval: G2,
+    /// gr2^alphar*Rp(s)
+    pub gr2_rop_shifted_eval: G2,
+    /// g2^h(s)
+    pub g2_h: G2,
+}
+
+impl Proof {
+    /// Affine encoding of the proof, compressed points take 32 bytes in G1 and 64 bytes in G2
+    pub fn to_bytes(&self, compress: Compress) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.serialized_size(compress));
+        self.serialize_with_mode(&mut bytes, compress).unwrap();
+        bytes
+    }
+
+    /// Deserialize a proof written by `to_bytes`, the encoding follows from the length.
+    /// Every element has to be a non-identity point of the prime order subgroup
+    pub fn read_proof(bytes: &[u8]) -> Result<Proof, ProofError> {
+        let compress = if bytes.len() == Proof::encoded_size(Compress::Yes) {
+            Compress::Yes
+        } else if bytes.len() == Proof::encoded_size(Compress::No) {
+            Compress::No
+        } else {
+            return Err(ProofError::WrongLength(bytes.len()));
+        };
+        let proof = Proof::deserialize_with_mode(bytes, compress, Validate::No).map_err(ProofError::Serialization)?;
+        proof.validate()?;
+        Ok(proof)
+    }
+
+    //Encoded size, the same for every proof
+    fn encoded_size(compress: Compress) -> usize {
+        let fingerprint = CircuitFingerprint([0; 32]).serialized_size(compress);
+        let g1 = G::zero().serialized_size(compress);
+        let g2 = G2::zero().serialized_size(compress);
+        fingerprint + 6 * g1 + 3 * g2
+    }
+
+    /// Check every element of the proof, an honest proof never contains the identity
+    pub fn validate(&self) -> Result<(), ProofError> {
+        let check = |element: &'static str, result: Result<(), InvalidPoint>| result.map_err(|reason| ProofError::InvalidPoint { element, reason });
+        check("gl_lop_eval", validate_point(&self.gl_lop_eval, false))?;
+        check("gr_rop_eval", validate_point(&self.gr_rop_eval, false))?;
+        check("go_oop_eval", validate_point(&self.go_oop_eval, false))?;
+        check("gl_lop_shifted_eval", validate_point(&self.gl_lop_shifted_eval, false))?;
+        check("go_oop_shifted_eval", validate_point(&self.go_oop_shifted_eval, false))?;
+        check("g_z", validate_point(&self.g_z, false))?;
+        check("gr2_rop_eval", validate_point(&self.gr2_rop_eval, false))?;
+        check("gr2_rop_shifted_eval", validate_point(&self.gr2_rop_shifted_eval, false))?;
+        check("g2_h", validate_point(&self.g2_h, false))
+    }
+}
+
+/// Why a group element read from a key or proof was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidPoint {
+    /// Coordinates don't satisfy the curve equation
+    NotOnCurve,
+    /// Point of the curve outside the prime order subgroup
+    NotInSubgroup,
+    /// Identity where a non-trivial element is required
+    Identity,
+}
+
+impl fmt::Display for InvalidPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidPoint::NotOnCurve => write!(f, "point is not on the curve"),
+            InvalidPoint::NotInSubgroup => write!(f, "point is not in the prime order subgroup"),
+            InvalidPoint::Identity => write!(f, "point is the identity"),
+        }
+    }
+}
+
+/// Check a point that was deserialized without validation
+pub fn validate_point<P: SWCurveConfig>(point: &Projective<P>, identity_allowed: bool) -> Result<(), InvalidPoint> {
+    let point = point.into_affine();
+    if point.is_zero() {
+        return if identity_allowed { Ok(()) } else { Err(InvalidPoint::Identity) };
+    }
+    if !point.is_on_curve() {
+        return Err(InvalidPoint::NotOnCurve);
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(InvalidPoint::NotInSubgroup);
+    }
+    Ok(())
+}
+
+/// Error reading a proof
+#[derive(Debug)]
+pub enum ProofError {
+    /// Bytes don't decode to the proof elements
+    Serialization(SerializationError),
+    /// Length that is neither the compressed nor the uncompressed proof size
+    WrongLength(usize),
+    /// Element that isn't a valid group element
+    InvalidPoint { element: &'static str, reason: InvalidPoint },
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofError::Serialization(err) => write!(f, "Malformed proof: {}", err),
+            ProofError::WrongLength(length) => write!(f, "Malformed proof: {} bytes, expected {} compressed or {} uncompressed", length, Proof::encoded_size(Compress::Yes), Proof::encoded_size(Compress::No)),
+            ProofError::InvalidPoint { element, reason } => write!(f, "Invalid proof element {}: {}", element, reason),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// Error raised by `prove`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// Witness is missing a value
+    InvalidWitness(String),
+    /// Divisor of the given inverse wire is zero
+    DivisionByZero(String),
+    /// Constraints that don't hold for the witness
+    UnsatisfiedConstraints(Vec<UnsatisfiedConstraint>),
+    /// Key was generated for a different circuit
+    KeyMismatch(String),
+    /// Fingerprints of the proving key and the circuit differ
+    FingerprintMismatch { key: CircuitFingerprint, circuit: CircuitFingerprint },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidWitness(msg) => write!(f, "{}", msg),
+            Error::DivisionByZero(variable) => write!(f, "Division by zero: divisor of {:?} is 0 in the witness", variable),
+            Error::UnsatisfiedConstraints(unsatisfied) => {
+                write!(f, "Witness doesn't satisfy {} constraints", unsatisfied.len())?;
+                for constraint in unsatisfied {
+                    write!(f, "\n{}", constraint)?;
+                }
+                Ok(())
+            }
+            Error::KeyMismatch(msg) => write!(f, "Key doesn't match the circuit: {}", msg),
+            Error::FingerprintMismatch { key, circuit } => write!(f, "Proving key is for circuit {} but the circuit is {}", key, circuit),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Operand polynomial of a knowledge check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Left,
+    Right,
+    Output,
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Left => write!(f, "left"),
+            Operand::Right => write!(f, "right"),
+            Operand::Output => write!(f, "output"),
+        }
+    }
+}
+
+/// Reason `verify` rejected a proof
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationError {
+    /// Proof element that isn't a valid group element, or proof bytes that don't decode
+    MalformedProof(String),
+    /// Verifying key that is inconsistent with itself
+    InvalidKey(String),
+    /// Proof made for another circuit than the verifying key
+    FingerprintMismatch { key: CircuitFingerprint, proof: CircuitFingerprint },
+    /// Public variable without a value
+    MissingPublicInput(String),
+    /// Value given for a variable that isn't a public input
+    UnknownPublicInput(String),
+    /// Operand polynomial isn't a combination of the wire polynomials of the key
+    KnowledgeCheckFailed(Operand),
+    /// Operand polynomials with the public values aren't divisible by the vanishing polynomial
+    DivisibilityCheckFailed,
+    /// Operand polynomials don't use the same wire values
+    ConsistencyCheckFailed,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerificationError::MalformedProof(msg) => write!(f, "{}", msg),
+            VerificationError::InvalidKey(msg) => write!(f, "Invalid verification key: {}", msg),
+            VerificationError::FingerprintMismatch { key, proof } => write!(f, "Verification key is for circuit {} but the proof is for circuit {}", key, proof),
+            VerificationError::MissingPublicInput(variable) => write!(f, "Missing public value for variable: {:?}", variable),
+            VerificationError::UnknownPublicInput(variable) => write!(f, "Variable: {:?} is not a public input of the circuit", variable),
+            VerificationError::KnowledgeCheckFailed(operand) => write!(f, "Knowledge check of the {} operand failed", operand),
+            VerificationError::DivisibilityCheckFailed => write!(f, "Divisibility check failed, the proof doesn't hold for these public values"),
+            VerificationError::ConsistencyCheckFailed => write!(f, "Consistency check failed, the operands don't use the same wire values"),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+impl From<ProofError> for VerificationError {
+    fn from(err: ProofError) -> Self {
+        VerificationError::MalformedProof(err.to_string())
+    }
+}
+
+/// Run the trusted setup for the compiled circuit, the public variables are left out of the proving key
+pub fn setup<R: Rng>(compiled: &CompiledCircuit, rng: &mut R) -> (ProvingKey, VerifyingKey) {
+    let circuit = &compiled.circuit;
+    let vanishing_p = &compiled.vanishing_polynomial;
+
+    let g = G::generator(); //Generator on the curve
+    let g2 = G2::generator(); //Generator on the curve G2projective
+
+    let s: Fr = Fr::rand(rng);
+
+    let rohl: Fr = Fr::rand(rng);
+    let rohr: Fr = Fr::rand(rng);
+    let roho: Fr = rohl * rohr;
+    let alphal: Fr = Fr::rand(rng);
+    let alphar: Fr = Fr::rand(rng);
+    let alphao: Fr = Fr::rand(rng);
+    let beta: Fr = Fr::rand(rng);
+    let gamma: Fr = Fr::rand(rng);
+    let t_eval: Fr = vanishing_p.evaluate(&s);
+
+    let gl = g * rohl;
+    let gr = g * rohr;
+    let go = g * roho;
+
+    let gr2 = g2 * rohr; //G2
+
+    let circuit_fingerprint = compiled.fingerprint;
+    let mut proving_key = ProvingKey {
+        circuit_fingerprint,
+        gl_left_operand_poly_eval: Vec::new(),
+        gr_right_operand_poly_eval: Vec::new(),
+        go_output_operand_poly_eval: Vec::new(),
+        gl_alpha_left_operand_poly_eval: Vec::new(),
+        gr_alpha_right_operand_poly_eval: Vec::new(),
+        go_alpha_output_operand_poly_eval: Vec::new(),
+        g_beta_operand_poly_eval: Vec::new(),
+        gl_t_eval: gl * t_eval,
+        gr_t_eval: gr * t_eval,
+        go_t_eval: go * t_eval,
+        gl_alphal_t_eval: gl * (alphal * t_eval),
+        gr_alphar_t_eval: gr * (alphar * t_eval),
+        go_alphao_t_eval: go * (alphao * t_eval),
+        gl_beta_t_eval: gl * (beta * t_eval),
+        gr_beta_t_eval: gr * (beta * t_eval),
+        go_beta_t_eval: go * (beta * t_eval),
+        g_alphal: g * alphal,
+        g_alphar: g * alphar,
+        g_alphao: g * alphao,
+        gr2_t_eval: gr2 * t_eval,
+        gr2_alphar_t_eval: gr2 * (alphar * t_eval),
+        gr2_right_operand_poly_eval: Vec::new(),
+        gr2_alpha_right_operand_poly_eval: Vec::new(),
+        //Compute g^s^k for 1 <= k <= degree of the vanishing polynomial, the highest power of h(s)
+        g2sk: (1..=vanishing_p.degree()).map(|k| g2 * s.pow([k as u64])).collect(),
+    };
+
+    //Evaluations of the public variables, the verifier computes their part of the proof
+    let mut public_eval: HashMap<String, (Fr, Fr, Fr)> = HashMap::new();
+
+    //Compute evaluations for every wire : gl^li(s) , gr^ri(s) , go^oi(s) with their alpha shifts and g^beta*(li(s)+ri(s)+oi(s))
+    let [l_evals, r_evals, o_evals] = compiled.wire_evaluations(&s);
+    for (i, variable) in compiled.wire_list.iter().enumerate() {
+        let (l_eval, r_eval, o_eval) = (l_evals[i], r_evals[i], o_evals[i]);
+        if circuit.is_public(variable) {
+            public_eval.insert(variable.clone(), (l_eval, r_eval, o_eval));
+            continue;
+        }
+
+        proving_key.gl_left_operand_poly_eval.push(gl * l_eval);
+        proving_key.gl_alpha_left_operand_poly_eval.push(gl * (alphal * l_eval));
+
+        proving_key.gr_right_operand_poly_eval.push(gr * r_eval);
+        proving_key.gr_alpha_right_operand_poly_eval.push(gr * (alphar * r_eval));
+        proving_key.gr2_right_operand_poly_eval.push(gr2 * r_eval); //G2
+        proving_key.gr2_alpha_right_operand_poly_eval.push(gr2 * (alphar * r_eval)); //G2
+
+        proving_key.go_output_operand_poly_eval.push(go * o_eval);
+        proving_key.go_alpha_output_operand_poly_eval.push(go * (alphao * o_eval));
+
+        //Single beta term per wire ties its value across the three operands
+        proving_key.g_beta_operand_poly_eval.push(gl * (beta * l_eval) + gr * (beta * r_eval) + go * (beta * o_eval));
+    }
+
+    let mut verifying_key = VerifyingKey {
+        circuit_fingerprint,
+        public_variables: circuit.public_variables.clone(),
+        g,
+        go_t_eval: proving_key.go_t_eval,
+        g_alphar: proving_key.g_alphar,
+        gl_public_left_operand_poly_eval: Vec::new(),
+        go_public_output_operand_poly_eval: Vec::new(),
+        g2,
+        g2_alphal: g2 * alphal,
+        g2_alphao: g2 * alphao,
+        g2_gamma: g2 * gamma,
+        g2_beta_gamma: g2 * (beta * gamma),
+        gr2_public_right_operand_poly_eval: Vec::new(),
+    };
+
+    //Compute public evaluations : gl^li(s) , go^oi(s) , gr2^ri(s) in declaration order
+    //A public name without a wire has zero polynomials
+    for variable in &circuit.public_variables {
+        let (l_eval, r_eval, o_eval) = public_eval.get(variable).copied().unwrap_or_default();
+        verifying_key.gl_public_left_operand_poly_eval.push(gl * l_eval);
+        verifying_key.go_public_output_operand_poly_eval.push(go * o_eval);
+        verifying_key.gr2_public_right_operand_poly_eval.push(gr2 * r_eval);
+    }
+
+    (proving_key, verifying_key)
+}
+
+//Value of a linear combination of witness variables
+fn evaluate_terms(terms: &[(Fr, String)], witness_values: &Witness) -> Result<Fr, Error> {
+    let mut value = Fr::zero();
+    for (coeff, variable) in terms {
+        match witness_values.get(variable) {
+            Some(var_value) => value += *coeff * var_value,
+            None => return Err(Error::InvalidWitness(format!("Variable: {:?} not found in the witness", variable))),
+        }
+    }
+    Ok(value)
+}
+
+//Fill the inverse wires introduced by division constraints
+fn compute_inverse_wires(witness_values: &mut Witness, parsed_operations: &[Operation]) -> Result<(), Error> {
+    for operation in parsed_operations {
+        //Non-zero check: divisor * divisor^-1 == one
+        let inverse_variable = match operation.right.as_slice() {
+            [(_, variable)] if variable.ends_with(INVERSE_WIRE_SUFFIX) => variable,
+            _ => continue,
+        };
+
+        let divisor_value = evaluate_terms(&operation.left, witness_values)?;
+        match divisor_value.inverse() {
+            Some(inverse) => witness_values.insert(inverse_variable.clone(), inverse),
+            None => return Err(Error::DivisionByZero(inverse_variable.clone())),
+        };
+    }
+    Ok(())
+}
+
+//Value of every wire in wire list order
+fn wire_values(wire_list: &[String], witness_values: &Witness) -> Result<Vec<Fr>, Error> {
+    wire_list.iter().map(|variable| match witness_values.get(variable) {
+        Some(var_value) => Ok(*var_value),
+        None => Err(Error::InvalidWitness(format!("Variable: {:?} not found in the witness", variable))),
+    }).collect()
+}
+
+//Encrypted evaluation: product of the key elements raised to the wire values
+fn compute_encrypted_evaluation<T: Group<ScalarField = Fr>>(g_operand_poly_eval: &[T], values: &[Fr]) -> T {
+    let mut g_eval = T::zero();
+    for (g_eval_part, value) in g_operand_poly_eval.iter().zip(values.iter()) {
+        g_eval += g_eval_part.mul(value);
+    }
+    g_eval
+}
+
+/// Generate a proof that the witness satisfies the compiled circuit, the blinding factors are drawn from rng
+pub fn prove<R: Rng>(proving_key: &ProvingKey, compiled: &CompiledCircuit, witness: &Witness, rng: &mut R) -> Result<Proof, Error> {
+    let circuit = &compiled.circuit;
+    let circuit_fingerprint = compiled.fingerprint;
+    if proving_key.circuit_fingerprint != circuit_fingerprint {
+        return Err(Error::FingerprintMismatch { key: proving_key.circuit_fingerprint, circuit: circuit_fingerprint });
+    }
+    let parsed_operations = &circuit.operations;
+    let mut witness_values = witness.clone();
+    witness_values.insert(String::from(ONE_WIRE), Fr::one()); //Constant one wire
+    compute_inverse_wires(&mut witness_values, parsed_operations)?;
+
+    //Check every constraint against the witness before building the proof
+    let unsatisfied = circuit.check_witness(&witness_values).map_err(Error::InvalidWitness)?;
+    if !unsatisfied.is_empty() {
+        return Err(Error::UnsatisfiedConstraints(unsatisfied));
+    }
+
+    let wire_list = &compiled.wire_list;
+    let vanishing_p = &compiled.vanishing_polynomial;
+    let values = wire_values(wire_list, &witness_values)?;
+
+    //Proving key only holds the private variables, the verifier computes the public part
+    let private_values: Vec<Fr> = wire_list.iter().zip(values.iter()).filter(|(variable, _)| !circuit.is_public(variable)).map(|(_, value)| *value).collect();
+    //Zipping the sections would silently drop the wires missing from a shorter one
+    let wire_sections = [
+        proving_key.gl_left_operand_poly_eval.len(),
+        proving_key.gr_right_operand_poly_eval.len(),
+        proving_key.go_output_operand_poly_eval.len(),
+        proving_key.gl_alpha_left_operand_poly_eval.len(),
+        proving_key.gr_alpha_right_operand_poly_eval.len(),
+        proving_key.go_alpha_output_operand_poly_eval.len(),
+        proving_key.g_beta_operand_poly_eval.len(),
+        proving_key.gr2_right_operand_poly_eval.len(),
+        proving_key.gr2_alpha_right_operand_poly_eval.len(),
+    ];
+    if wire_sections.iter().any(|len| *len != wire_sections[0]) {
+        return Err(Error::KeyMismatch(format!("private wire sections of the key have different lengths {:?}", wire_sections)));
+    }
+    if proving_key.gl_left_operand_poly_eval.len() != private_values.len() || proving_key.g2sk.len() != vanishing_p.degree() {
+        return Err(Error::KeyMismatch(format!(
+            "key has {} private wires and {} vanishing roots, circuit has {} and {}",
+            proving_key.gl_left_operand_poly_eval.len(),
+            proving_key.g2sk.len(),
+            private_values.len(),
+            vanishing_p.degree()
+        )));
+    }
+
+    //Compute operand polynomial
+    let [left_operand_polynomial, right_operand_polynomial, output_operand_polynomial] = compiled.operand_polynomials(&values);
+
+    let polynomial_p = &left_operand_polynomial.mul(&right_operand_polynomial) - &output_operand_polynomial;
+    let polynomial_h_p1 = compiled.divide_by_vanishing_polynomial(&polynomial_p);
+
+    // Compute random deltal,deltar,deltao
+    let delta_l: Fr = Fr::rand(rng);
+    let delta_r: Fr = Fr::rand(rng);
+    let delta_o: Fr = Fr::rand(rng);
+    let delta_l_r: Fr = delta_l * delta_r;
+
+    let polynomial_h_p2 = &left_operand_polynomial * delta_r + &right_operand_polynomial * delta_l + vanishing_p * delta_l_r;
+    let polynomial_h = &(polynomial_h_p1 + polynomial_h_p2) - &DensePolynomial::from_coefficients_vec(vec![delta_o]);
+
+    let pk = proving_key;
+
+    //Compute gl^Lp(s), gl^L'p(s), gr^Rp(s), gr2^Rp(s), gr2^R'p(s), go^Op(s), go^O'p(s)
+    let gl_lop_eval = compute_encrypted_evaluation(&pk.gl_left_operand_poly_eval, &private_values) + pk.gl_t_eval.mul(delta_l);
+    let gl_lop_shifted_eval = compute_encrypted_evaluation(&pk.gl_alpha_left_operand_poly_eval, &private_values) + pk.gl_alphal_t_eval.mul(delta_l);
+    let gr_rop_eval = compute_encrypted_evaluation(&pk.gr_right_operand_poly_eval, &private_values) + pk.gr_t_eval.mul(delta_r);
+    let gr2_rop_eval = compute_encrypted_evaluation(&pk.gr2_right_operand_poly_eval, &private_values) + pk.gr2_t_eval.mul(delta_r);
+    let gr2_rop_shifted_eval = compute_encrypted_evaluation(&pk.gr2_alpha_right_operand_poly_eval, &private_values) + pk.gr2_alphar_t_eval.mul(delta_r);
+    let go_oop_eval = compute_encrypted_evaluation(&pk.go_output_operand_poly_eval, &private_values) + pk.go_t_eval.mul(delta_o);
+    let go_oop_shifted_eval = compute_encrypted_evaluation(&pk.go_alpha_output_operand_poly_eval, &private_values) + pk.go_alphao_t_eval.mul(delta_o);
+
+    //Compute g^h(s)
+    let mut g2_h = G2::zero();
+    for (i, coeff) in polynomial_h.coeffs.iter().enumerate() {
+        if i == 0 {
+            g2_h += G2::generator().mul(coeff);
+        } else {
+            g2_h += pk.g2sk[i - 1].mul(coeff);
+        }
+    }
+
+    //Compute g^Z(s)
+    let z_1 = pk.gl_beta_t_eval * delta_l + pk.gr_beta_t_eval * delta_r + pk.go_beta_t_eval * delta_o;
+    let z_2 = compute_encrypted_evaluation(&pk.g_beta_operand_poly_eval, &private_values);
+    let g_z = z_1 + z_2;
+
+    Ok(Proof {
+        circuit_fingerprint,
+        gl_lop_eval,
+        gr_rop_eval,
+        go_oop_eval,
+        gl_lop_shifted_eval,
+        go_oop_shifted_eval,
+        g_z,
+        gr2_rop_eval,
+        gr2_rop_shifted_eval,
+        g2_h,
+    })
+}
+
+/// Check the proof against the public inputs, the error names the first check that failed
+pub fn verify(verifying_key: &VerifyingKey, proof: &Proof, public_inputs: &PublicInputs) -> Result<(), VerificationError> {
+    proof.validate()?;
+    let vk = verifying_key;
+    if proof.circuit_fingerprint != vk.circuit_fingerprint {
+        return Err(VerificationError::FingerprintMismatch { key: vk.circuit_fingerprint, proof: proof.circuit_fingerprint });
+    }
+    let public_count = vk.public_variables.len();
+    if vk.gl_public_left_operand_poly_eval.len() != public_count || vk.go_public_output_operand_poly_eval.len() != public_count || vk.gr2_public_right_operand_poly_eval.len() != public_count {
+        return Err(VerificationError::InvalidKey(format!("key has {} public variables but not as many public evaluations", public_count)));
+    }
+
+    if let Some(variable) = public_inputs.keys().find(|variable| *variable == ONE_WIRE || !vk.public_variables.contains(variable)) {
+        return Err(VerificationError::UnknownPublicInput(variable.clone()));
+    }
+
+    //Public part of the operand polynomials: gl^Lv(s), gr2^Rv(s), go^Ov(s)
+    let mut gl_public_eval = G::zero();
+    let mut gr2_public_eval = G2::zero();
+    let mut go_public_eval = G::zero();
+    for (i, variable) in vk.public_variables.iter().enumerate() {
+        let value = match public_inputs.get(variable) {
+            Some(value) => *value,
+            None if variable == ONE_WIRE => Fr::one(),
+            None => return Err(VerificationError::MissingPublicInput(variable.clone())),
+        };
+        gl_public_eval += vk.gl_public_left_operand_poly_eval[i].mul(value);
+        go_public_eval += vk.go_public_output_operand_poly_eval[i].mul(value);
+        gr2_public_eval += vk.gr2_public_right_operand_poly_eval[i].mul(value);
+    }
+
+    //Asserting the same values G1 and G2 elements
+    if Bn254::pairing(vk.g, G2::generator()) != Bn254::pairing(G::generator(), vk.g2) {
+        return Err(VerificationError::InvalidKey(String::from("G1 and G2 generators don't match")));
+    }
+
+    //(Pairing check) Variable polynomial restriction check
+    if Bn254::pairing(proof.gl_lop_eval, vk.g2_alphal) != Bn254::pairing(proof.gl_lop_shifted_eval, vk.g2) {
+        return Err(VerificationError::KnowledgeCheckFailed(Operand::Left));
+    }
+    if Bn254::pairing(vk.g_alphar, proof.gr2_rop_eval) != Bn254::pairing(vk.g, proof.gr2_rop_shifted_eval) {
+        return Err(VerificationError::KnowledgeCheckFailed(Operand::Right));
+    }
+    if Bn254::pairing(proof.go_oop_eval, vk.g2_alphao) != Bn254::pairing(proof.go_oop_shifted_eval, vk.g2) {
+        return Err(VerificationError::KnowledgeCheckFailed(Operand::Output));
+    }
+
+    //(Pairing check) Variable consistency check e(gl^Lp(s) * gr^Rp(s) * go^Op(s), g^beta*gamma) === e(g^Z(s), g^gamma)
+    let g_l_r_o = proof.gl_lop_eval + proof.gr_rop_eval + proof.go_oop_eval;
+    if Bn254::pairing(g_l_r_o, vk.g2_beta_gamma) != Bn254::pairing(proof.g_z, vk.g2_gamma) {
+        return Err(VerificationError::ConsistencyCheckFailed);
+    }
+
+    //Assert the two gr_rop_eval
+    if Bn254::pairing(proof.gr_rop_eval, G2::generator()) != Bn254::pairing(G::generator(), proof.gr2_rop_eval) {
+        return Err(VerificationError::ConsistencyCheckFailed);
+    }
+
+    //(Pairing check) Valid operation check  e(gl^L(s),gr^R(s)) === e(go^(t(s)),g^h(s)) * e(go^O(s),g) with L = Lp + Lv
+    let left_pairing_part = Bn254::pairing(proof.gl_lop_eval + gl_public_eval, proof.gr2_rop_eval + gr2_public_eval);
+    let right_pairing_part = Bn254::pairing(vk.go_t_eval, proof.g2_h) + Bn254::pairing(proof.go_oop_eval + go_public_eval, vk.g2);
+    if left_pairing_part != right_pairing_part {
+        return Err(VerificationError::DivisibilityCheckFailed);
+    }
+
+    Ok(())
+}
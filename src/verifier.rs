@@ -1,242 +1,116 @@
-use core::panic;
-use std::vec;
-use ark_bn254::g1::Config;
-use ark_bn254::g2::Config as Config2;
-use ark_ec::Group; // For the `.mul()` method
-use ark_ec::short_weierstrass::Projective;
 use std::fs::File;
-use std::io::{Cursor, Read};
-use ark_bn254::{Bn254, FqConfig,Fq2Config, G1Projective as G, G2Projective as G2};
-use ark_serialize::CanonicalDeserialize;
-use std::io::Result;
-use ark_ff::{Fp,Fp2,Fp2ConfigWrapper,QuadExtField, MontBackend};
-use ark_ec::pairing::Pairing;
+use std::io::{BufReader, Read};
 use base64::{engine::general_purpose, Engine as _}; // Import the Engine trait
-
-//For G1Projective and G2 projective elements
-#[derive(Debug)]
-#[derive(Clone)]
-#[derive(Copy)]
-enum ProjectiveConfigType {
-    GOne(Projective<Config>),
-    GTwo(Projective<Config2>)
+use std::collections::HashMap;
+use pikachu::{parse_field_element_mod_order,verify,Proof,PublicInputs,VerifyingKey,KeyFile,KeyFileError,VerificationError};
+
+//Exit codes, 1 is used for errors before the proof is checked
+const EXIT_MALFORMED_PROOF:i32 = 2;
+const EXIT_KNOWLEDGE_CHECK_FAILED:i32 = 3;
+const EXIT_DIVISIBILITY_CHECK_FAILED:i32 = 4;
+const EXIT_CONSISTENCY_CHECK_FAILED:i32 = 5;
+const EXIT_FINGERPRINT_MISMATCH:i32 = 6;
+
+fn exit_code(err:&VerificationError) -> i32{
+    match err {
+        VerificationError::MalformedProof(_) => EXIT_MALFORMED_PROOF,
+        VerificationError::KnowledgeCheckFailed(_) => EXIT_KNOWLEDGE_CHECK_FAILED,
+        VerificationError::DivisibilityCheckFailed => EXIT_DIVISIBILITY_CHECK_FAILED,
+        VerificationError::ConsistencyCheckFailed => EXIT_CONSISTENCY_CHECK_FAILED,
+        VerificationError::FingerprintMismatch { .. } => EXIT_FINGERPRINT_MISMATCH,
+        VerificationError::InvalidKey(_) | VerificationError::MissingPublicInput(_) | VerificationError::UnknownPublicInput(_) => 1,
+    }
 }
 
+fn parse_proof(proof:&str) -> Result<Proof,VerificationError>{
+    let proof_binary:Vec<u8> = general_purpose::STANDARD.decode(proof.trim()).map_err(|err| VerificationError::MalformedProof(format!("Malformed proof: {}",err)))?;
+    Ok(Proof::read_proof(&proof_binary)?)
+}
 
-fn parse_proof(proof:&str) -> Vec<ProjectiveConfigType>{
-    let proof_binary:Vec<u8> =  general_purpose::STANDARD.decode(proof).expect("Invalid proof !!");
-    let mut cursor = Cursor::new(&proof_binary[..]);
-    let mut iteration_no = 0;
-    let mut deserialized_proof:Vec<ProjectiveConfigType> = Vec::new();
-
-    //Deserialize proof elements
-    while (cursor.position() as usize) < cursor.get_ref().len(){ 
-        iteration_no = iteration_no+1;
-
-        //Read the length
-        let mut element_len =[0u8];
-
-        cursor.read_exact(&mut element_len).expect("Invalid proof !!"); // Read x length
-        let mut x_element: Vec<u8> = vec![0u8;element_len[0] as usize];
-        cursor.read_exact(&mut x_element).expect("Invalid proof !!"); //Read x 
-
-        cursor.read_exact(&mut element_len).expect("Invalid proof !!"); // Read y length
-        let mut y_element: Vec<u8> = vec![0u8;element_len[0] as usize];
-        cursor.read_exact(&mut y_element).expect("Invalid proof !!"); //Read y 
-
-        cursor.read_exact(&mut element_len).expect("Invalid proof !!"); // Read z length
-        let mut z_element: Vec<u8> = vec![0u8;element_len[0] as usize];
-        cursor.read_exact(&mut z_element).expect("Invalid proof !!"); //Read z
-
-        //Deseralize
-        let mut cursorx = Cursor::new(x_element);
-        let mut cursory = Cursor::new(y_element);
-        let mut cursorz = Cursor::new(z_element);
-
-        if iteration_no >= 7{
-            //G2 elements
-            let deserialized_x:QuadExtField<Fp2ConfigWrapper<Fq2Config>> = Fp2::deserialize_uncompressed(&mut cursorx).expect("Invalid proof !!");
-            let deserialized_y:QuadExtField<Fp2ConfigWrapper<Fq2Config>> = Fp2::deserialize_uncompressed(&mut cursory).expect("Invalid proof !!");
-            let deserialized_z:QuadExtField<Fp2ConfigWrapper<Fq2Config>> = Fp2::deserialize_uncompressed(&mut cursorz).expect("Invalid proof !!");
-    
-            let element:Projective<Config2> = G2::new_unchecked(deserialized_x, deserialized_y, deserialized_z); //Note only unchecked returns projective representation, since we construct from already existing group we can ignore the check
-            deserialized_proof.push(ProjectiveConfigType::GTwo(element)); //Push the element
-        }else{
-            //G1 elements
-            let deserialized_x:Fp<MontBackend<FqConfig,4>,4> = Fp::deserialize_uncompressed(&mut cursorx).expect("Invalid proof !!");
-            let deserialized_y:Fp<MontBackend<FqConfig,4>,4> = Fp::deserialize_uncompressed(&mut cursory).expect("Invalid proof !!");
-            let deserialized_z:Fp<MontBackend<FqConfig,4>,4> = Fp::deserialize_uncompressed(&mut cursorz).expect("Invalid proof !!");
-    
-            let element:Projective<Config> = G::new_unchecked(deserialized_x, deserialized_y, deserialized_z); //Note only unchecked returns projective representation, since we construct from already existing group we can ignore the check
-            deserialized_proof.push(ProjectiveConfigType::GOne(element)); //Push the element
-        }
+//Proof string from a file, - is stdin
+fn read_proof_string(source:&str) -> std::io::Result<String>{
+    let mut proof_string = String::new();
+    if source == "-" {
+        std::io::stdin().read_to_string(&mut proof_string)?;
+    } else {
+        File::open(source)?.read_to_string(&mut proof_string)?;
     }
-
-    deserialized_proof
+    Ok(proof_string)
 }
 
-const DELIMITER: &[u8] = &[0]; // Inner delimiter for separating vec of elements
-fn load_key_from_file(file_name:&str,g1g2_seperator_index:u8) -> Result<Vec<Vec<ProjectiveConfigType>>>{
-    let mut file = File::open(file_name).expect("Invalid proof !!");
-
-    let mut final_key:Vec<Vec<ProjectiveConfigType>> = Vec::new();
-    let mut key_vec_inner:Vec<ProjectiveConfigType> = Vec::new();
-
-    //Buffer to load the file
-    let mut buffer:Vec<u8> = Vec::new();
-    file.read_to_end(&mut buffer).expect("Invalid proof !!");
-
-    // let mut cursor = &buffer[..];
-    let mut cursor = Cursor::new(&buffer[..]);
-
-    let mut iteration_no = 0;
-
-    // Deserialize each values
-    while (cursor.position() as usize) < cursor.get_ref().len(){ //Ignoring the last 0 delimiter
-
-        //Read the length
-        let mut element_len =[0u8];
-        cursor.read_exact(&mut element_len).expect("Invalid proof !!"); // Read x length
+fn load_key_from_file(file_name:&str) -> Result<VerifyingKey,KeyFileError>{
+    let file = File::open(file_name)?;
+    VerifyingKey::read_key_file(BufReader::new(file))
+}
 
-        //If delimeter found
-        if element_len == DELIMITER{
-            //Push and clear the inner vec
-            final_key.push(key_vec_inner.clone());
-            key_vec_inner.clear();
+//Parse public values given as name=value
+fn parse_public_values(public_inputs:&[String]) -> Result<PublicInputs,String>{
+    let mut public_values:PublicInputs = HashMap::new();
+    for public_input in public_inputs{
+        let (variable,value) = match public_input.split_once('=') {
+            Some((variable,value)) => (variable.trim(),value.trim()),
+            None => return Err(format!("Invalid public value {:?}, expected name=value",public_input))
+        };
+        let value = match parse_field_element_mod_order(value) {
+            Some(value) => value,
+            None => return Err(format!("Invalid public value {:?} for variable {:?}, expected a decimal or 0x hex integer",value,variable))
+        };
+        public_values.insert(String::from(variable),value);
+    }
+    Ok(public_values)
+}
 
-            //If last index continue/break
-            if cursor.position() as usize == cursor.get_ref().len() {
-                continue;
-            }            
-            //Else next element length
-            cursor.read_exact(&mut element_len).expect("Invalid proof !!"); // Read again length
+//The proof is given inline, or read from proof_file (or stdin for -)
+pub fn main(proof:Option<&str>,proof_file:Option<&str>,public_inputs:&[String],verifying_key_file:&str){
+    let proof_string = match (proof,proof_file) {
+        (Some(proof),None) if proof != "-" => String::from(proof),
+        (Some(source),None) | (None,Some(source)) => match read_proof_string(source) {
+            Ok(proof_string) => proof_string,
+            Err(err) => {
+                eprintln!("Failed to read proof {}: {}",source,err);
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("Give the proof either as an argument or with --proof-file");
+            std::process::exit(1);
         }
+    };
 
-        iteration_no = iteration_no+1;
-
-        let mut x_element: Vec<u8> = vec![0u8;element_len[0] as usize];
-        cursor.read_exact(&mut x_element).expect("Invalid proof !!"); //Read x 
-
-        cursor.read_exact(&mut element_len).expect("Invalid proof !!"); // Read y length
-        let mut y_element: Vec<u8> = vec![0u8;element_len[0] as usize];
-        cursor.read_exact(&mut y_element).expect("Invalid proof !!"); //Read y 
-
-        cursor.read_exact(&mut element_len).expect("Invalid proof !!"); // Read z length
-        let mut z_element: Vec<u8> = vec![0u8;element_len[0] as usize];
-        cursor.read_exact(&mut z_element).expect("Invalid proof !!"); //Read z
-
-        //Deseralize
-        let mut cursorx = Cursor::new(x_element);
-        let mut cursory = Cursor::new(y_element);
-        let mut cursorz = Cursor::new(z_element);
-
-        if iteration_no >= g1g2_seperator_index {
-            //For G2 elements in verification key
-            let deserialized_x:QuadExtField<Fp2ConfigWrapper<Fq2Config>> = Fp2::deserialize_uncompressed(&mut cursorx).expect("Invalid proof !!");
-            let deserialized_y:QuadExtField<Fp2ConfigWrapper<Fq2Config>> = Fp2::deserialize_uncompressed(&mut cursory).expect("Invalid proof !!");
-            let deserialized_z:QuadExtField<Fp2ConfigWrapper<Fq2Config>> = Fp2::deserialize_uncompressed(&mut cursorz).expect("Invalid proof !!");
-    
-            let element:Projective<Config2> = G2::new_unchecked(deserialized_x, deserialized_y, deserialized_z); //Note only unchecked returns projective representation, since we construct from already existing group we can ignore the check
-            key_vec_inner.push(ProjectiveConfigType::GTwo(element)); //Push the element
-        }else{
-    
-            let deserialized_x:Fp<MontBackend<FqConfig,4>,4> = Fp::deserialize_uncompressed(&mut cursorx).expect("Invalid proof !!");
-            let deserialized_y:Fp<MontBackend<FqConfig,4>,4> = Fp::deserialize_uncompressed(&mut cursory).expect("Invalid proof !!");
-            let deserialized_z:Fp<MontBackend<FqConfig,4>,4> = Fp::deserialize_uncompressed(&mut cursorz).expect("Invalid proof !!");
-    
-            let element:Projective<Config> = G::new_unchecked(deserialized_x, deserialized_y, deserialized_z); //Note only unchecked returns projective representation, since we construct from already existing group we can ignore the check
-            key_vec_inner.push(ProjectiveConfigType::GOne(element)); //Push the element
-
+    let public_values = match parse_public_values(public_inputs) {
+        Ok(public_values) => public_values,
+        Err(msg) => {
+            eprintln!("{}",msg);
+            std::process::exit(1);
+        }
+    };
+
+    let proof = match parse_proof(&proof_string) {
+        Ok(proof) => proof,
+        Err(err) => {
+            eprintln!("{}",err);
+            eprintln!("Invalid proof !!");
+            std::process::exit(exit_code(&err));
+        }
+    };
+
+    //Read verification key, it carries the public variables of the statement
+    let vk = match load_key_from_file(verifying_key_file) {
+        Ok(vk) => vk,
+        Err(err) => {
+            eprintln!("Failed to read verification key {}: {}",verifying_key_file,err);
+            std::process::exit(1);
+        }
+    };
+
+    match verify(&vk,&proof,&public_values) {
+        Ok(()) => println!("Valid proof !!"),
+        Err(err) => {
+            eprintln!("{}",err);
+            if exit_code(&err) != 1 {
+                eprintln!("Invalid proof !!");
+            }
+            std::process::exit(exit_code(&err));
         }
-
-    }
-    Ok(final_key)
-}
-fn extract_g1_element(element:ProjectiveConfigType)->Projective<Config>{
-    match element {
-        ProjectiveConfigType::GOne(ref elem) => elem.clone(),
-        _ => panic!("Expected GOne element but found a different variant."),
-    }
-}
-fn extract_g2_element(element:ProjectiveConfigType)->Projective<Config2>{
-    match element {
-        ProjectiveConfigType::GTwo(ref elem) => elem.clone(),
-        _ => panic!("Expected GTwo element but found a different variant."),
     }
 }
-pub fn main(proof_string:&str){
-
-    let deserialized_proof = parse_proof(proof_string);
-    //Read verification key
-    let verification_key = load_key_from_file("verification_key.bin",4).expect("Invalid proof !!");
-
-    //Proofs:
-    let gl_lop_eval = extract_g1_element(deserialized_proof[0]);
-    let gr_rop_eval = extract_g1_element(deserialized_proof[1]);
-    let go_oop_eval = extract_g1_element(deserialized_proof[2]);
-    let gl_lop_shifted_eval = extract_g1_element(deserialized_proof[3]);
-    let go_oop_shifted_eval = extract_g1_element(deserialized_proof[4]);
-    let g_z = extract_g1_element(deserialized_proof[5]);
-    let gr2_rop_eval = extract_g2_element(deserialized_proof[6]); //G2
-    let gr2_rop_shifted_eval = extract_g2_element(deserialized_proof[7]); //G2
-    let g2_h = extract_g2_element(deserialized_proof[8]); //G2
-
-    //Verification key:
-    let generator_g1 = extract_g1_element(verification_key[0][0].clone());
-    let generator_g2: Projective<Config2> = extract_g2_element(verification_key[2][0].clone());
-    let g_alphal_g2 = extract_g2_element(verification_key[2][1].clone());
-    // let g_alphar_g2 = extract_g2_element(verification_key[3][2].clone());
-    let g_alphar_g1 = extract_g1_element(verification_key[1][1].clone());
-    let go_t_eval = extract_g1_element(verification_key[1][0].clone());
-    let g_alphao_g2 = extract_g2_element(verification_key[2][3].clone());
-    let g2_gamma = extract_g2_element(verification_key[2][4].clone());
-    let g2_beta_gamma = extract_g2_element(verification_key[2][5].clone());
-
-
-    //(Pairing check) Variable polynomial restriction check
-    let left_part_pairing_l = Bn254::pairing(gl_lop_eval, g_alphal_g2);
-    let right_part_pairing_l = Bn254::pairing(gl_lop_shifted_eval, generator_g2);
-
-    let left_part_pairing_r = Bn254::pairing(g_alphar_g1,gr2_rop_eval);
-    let right_part_pairing_r = Bn254::pairing(generator_g1,gr2_rop_shifted_eval);
-
-    let left_part_pairing_o = Bn254::pairing(go_oop_eval, g_alphao_g2);
-    let right_part_pairing_o = Bn254::pairing(go_oop_shifted_eval, generator_g2);
-
-
-    assert_eq!(left_part_pairing_l,right_part_pairing_l,"Invalid proof !!"); //Check
-    assert_eq!(left_part_pairing_r,right_part_pairing_r,"Invalid proof !!"); //Check
-    assert_eq!(left_part_pairing_o,right_part_pairing_o,"Invalid proof !!"); //Check
-
-    //Asserting the same values G1 and G2 elements 
-    let g1_gen = G::generator();
-    let g2_gen = G2::generator();
-
-    let generator_l = Bn254::pairing(generator_g1,g2_gen);
-    let generator_r = Bn254::pairing(g1_gen,generator_g2);
-
-    assert_eq!(generator_l,generator_r,"Invalid proof !!"); //Check
-
-    //(Pairing check) Valid operation check  e(gl^Lp(s),gr^Rp(s)) === e(go^(t(s)),g^h(s)) * e(go^O(s),g)
-    let left_pairing_part = Bn254::pairing(gl_lop_eval, gr2_rop_eval);
-    let right_pairing_part_1 = Bn254::pairing(go_t_eval, g2_h);
-    let right_pairing_part_2 = Bn254::pairing(go_oop_eval, generator_g2);
-    let right_pairing_part = right_pairing_part_1 + right_pairing_part_2;
-
-    assert_eq!(left_pairing_part,right_pairing_part,"Invalid proof !!"); //Check
-
-    //Check pairing
-    let g_l_r_o = gl_lop_eval + gr_rop_eval + go_oop_eval;
-    let variable_pairing_left_part = Bn254::pairing(g_l_r_o, g2_beta_gamma);
-    let variable_pairing_right_part = Bn254::pairing(g_z, g2_gamma);
-
-    assert_eq!(variable_pairing_left_part,variable_pairing_right_part,"Invalid proof !!"); //Check
-
-    //Assert the two gr_rop_eval
-    let gr_left_part = Bn254::pairing(gr_rop_eval, g2_gen);
-    let gr_right_part = Bn254::pairing(g1_gen, gr2_rop_eval);
-
-    assert_eq!(gr_left_part,gr_right_part,"Invalid proof !!"); //Check
-
-    println!("Valid proof !!");
-}
\ No newline at end of file
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use serde_json::{Map, Value};
+use ark_bn254::Fr as ScalarField;
+use ark_std::One;
+use pikachu::{parse_circuit,compute_wire_list,solve_witness,format_field_element,ONE_WIRE,INVERSE_WIRE_SUFFIX};
+use crate::prover::parse_witness_value;
+
+//Convert the input values to field elements, every input must be a variable of the circuit
+fn parse_inputs(inputs:&str,wire_list:&[String]) -> std::result::Result<HashMap<String,ScalarField>,String>{
+    let input_values:HashMap<String,Value> = match serde_json::from_str(inputs) {
+        Ok(input_values) => input_values,
+        Err(err) => return Err(format!("Invalid inputs, expected a JSON object of variable values: {}",err))
+    };
+
+    let mut witness_values:HashMap<String,ScalarField> = HashMap::new();
+    for (variable,var_value) in input_values{
+        if !wire_list.contains(&variable) || variable == ONE_WIRE {
+            return Err(format!("Variable: {:?} is not an input of the circuit",variable));
+        }
+        match parse_witness_value(&var_value) {
+            Some(value) => witness_values.insert(variable,value),
+            None => return Err(format!("Invalid value {} for variable: {:?}, expected a decimal or 0x hex integer",var_value,variable))
+        };
+    }
+    Ok(witness_values)
+}
+
+pub fn main(inputs:&str,circuit_file:&str,out_file:&str){
+
+    //Parsing circuit
+    eprintln!("Analyzing circuit");
+    let circuit = match parse_circuit(circuit_file) {
+        Ok(circuit) => circuit,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Circuit analyzed");
+    let wire_list = compute_wire_list(&circuit.operations);
+
+    let mut witness_values = match parse_inputs(inputs,&wire_list) {
+        Ok(witness_values) => witness_values,
+        Err(msg) => {
+            eprintln!("{}",msg);
+            std::process::exit(1);
+        }
+    };
+    witness_values.insert(String::from(ONE_WIRE), ScalarField::one()); //Constant one wire
+
+    println!("Solving circuit");
+    if let Err(msg) = solve_witness(&circuit.operations,&mut witness_values) {
+        eprintln!("{}",msg);
+        std::process::exit(1);
+    }
+
+    //The prover fills the one wire and inverse wires itself
+    let mut witness = Map::new();
+    for variable in wire_list.iter().filter(|v| *v != ONE_WIRE && !v.ends_with(INVERSE_WIRE_SUFFIX)){
+        witness.insert(variable.clone(),Value::String(format_field_element(witness_values[variable])));
+    }
+
+    let written = File::create(out_file).and_then(|mut file| file.write_all(serde_json::to_string_pretty(&witness).unwrap().as_bytes()));
+    if let Err(err) = written {
+        eprintln!("Failed to write witness {}: {}",out_file,err);
+        std::process::exit(1);
+    }
+    println!("Witness generated !!");
+}